@@ -676,7 +676,12 @@ impl System<ProgramState, Message> for OutputSystem {
             if !program_state.batch_mode {
                 self.flush_output();
             }
-            message_queue.push(Message::PollInput);
+            let poll_pending = message_queue
+                .iter_next()
+                .any(|message| matches!(message, Message::PollInput));
+            if !poll_pending {
+                message_queue.push(Message::PollInput);
+            }
         }
     }
 }
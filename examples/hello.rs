@@ -117,7 +117,7 @@ impl System<ProgramState, Message> for HelloSystem {
             message_queue.push(Message::Log("Hello, World!".to_string()));
         }
         // If there are no messages to process, initiate shutdown.
-        else if 0 == message_queue.iter().count() {
+        else if message_queue.is_empty() {
             message_queue.push(Message::Shutdown);
         }
     }
@@ -0,0 +1,120 @@
+// src/bench.rs
+
+// The `bench.rs` module provides `BenchHarness`, a small tool for comparing `System`
+// implementations. It is only compiled with the `testing` feature.
+
+// - Synthetic Load: For every tick the harness asks a generator function for that tick's
+//   messages, delivers them as the current tick, and updates the system under test once.
+
+// - Measurements: The harness counts the messages the system was handed and reads a `Clock`
+//   before and after the run. The resulting `BenchReport` gives messages per tick and, when
+//   the clock moved, messages per second.
+
+use crate::{clock::Clock, message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchReport {
+    pub ticks: u64,
+    pub messages: u64,
+    pub elapsed_ms: u64,
+}
+
+impl BenchReport {
+    pub fn messages_per_tick(&self) -> f64 {
+        if 0 == self.ticks {
+            0.0
+        } else {
+            self.messages as f64 / self.ticks as f64
+        }
+    }
+
+    // `None` when the run finished within a single clock reading.
+    pub fn messages_per_second(&self) -> Option<f64> {
+        if 0 == self.elapsed_ms {
+            None
+        } else {
+            Some(self.messages as f64 * 1000.0 / self.elapsed_ms as f64)
+        }
+    }
+}
+
+pub struct BenchHarness<C> {
+    clock: C,
+    ticks: u64,
+}
+
+impl<C> BenchHarness<C>
+where
+    C: Clock,
+{
+    pub fn new(clock: C, ticks: u64) -> Self {
+        Self { clock, ticks }
+    }
+
+    // Runs `system` for the configured number of ticks. `generate` receives the tick number,
+    // starting at one, and returns the messages delivered to the system on that tick. Messages
+    // the system pushes itself are discarded so that every tick sees the same kind of load.
+    pub fn run<ProgramState, Message, S, GenerateFunc>(
+        &mut self,
+        system: &mut S,
+        program_state: &mut ProgramState,
+        mut generate: GenerateFunc,
+    ) -> BenchReport
+    where
+        S: System<ProgramState, Message>,
+        GenerateFunc: FnMut(u64) -> Vec<Message>,
+    {
+        let mut message_queue = MessageQueue::new();
+        let mut messages = 0;
+        let start_ms = self.clock.now_ms();
+
+        for tick in 1..=self.ticks {
+            message_queue.next_tick_queue.clear();
+            message_queue.next_tick_queue.extend(generate(tick));
+            message_queue.next_tick();
+            messages += message_queue.current_tick_queue.len() as u64;
+            system.update(program_state, &mut message_queue);
+        }
+
+        BenchReport {
+            ticks: self.ticks,
+            messages,
+            elapsed_ms: self.clock.now_ms() - start_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use alloc::vec;
+
+    struct SumSystem;
+
+    impl System<i64, i64> for SumSystem {
+        fn update(&mut self, program_state: &mut i64, messages: &mut MessageQueue<i64>) {
+            for message in messages.iter() {
+                *program_state += message;
+            }
+        }
+    }
+
+    #[test]
+    fn test_bench_trivial_system() {
+        let mut harness = BenchHarness::new(ManualClock::stepping(0, 50), 10);
+        let mut program_state = 0;
+
+        let report = harness.run(&mut SumSystem, &mut program_state, |tick| {
+            vec![tick as i64; 3]
+        });
+
+        assert_eq!(program_state, 3 * 55);
+        assert_eq!(report.ticks, 10);
+        assert_eq!(report.messages, 30);
+        assert!(0.0 < report.messages_per_tick());
+        assert_eq!(report.messages_per_tick(), 3.0);
+        assert_eq!(report.messages_per_second(), Some(600.0));
+    }
+}
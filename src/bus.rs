@@ -0,0 +1,152 @@
+// src/bus.rs
+
+// The `bus.rs` module adds request/response correlation on top of the ordinary `MessageQueue`,
+// for flows like the calculator's command and result. A `SystemBus<M>` carries `BusMessage<M>`
+// values, each tagged with a `CorrelationId`, so a requesting system can tell which of several
+// responses answers which of its requests, even when they arrive ticks later.
+
+// - Requests: `request` queues a message under an id and remembers the id as pending.
+//   `next_id` hands out ids that are unique per bus.
+
+// - Responses: A responding system reads the requests of the current tick and answers each
+//   with `respond`, reusing the request's id. Responders need no bus of their own.
+
+// - Matching: `match_response` removes the response carrying a given id from the current tick
+//   and returns its message, leaving every other message in place for other systems.
+
+use crate::message_queue::MessageQueue;
+use core::marker::PhantomData;
+use hashbrown::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(pub u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusMessage<Message> {
+    Request(CorrelationId, Message),
+    Response(CorrelationId, Message),
+}
+
+pub struct SystemBus<Message> {
+    next_id: u64,
+    pending: HashSet<CorrelationId>,
+    _message: PhantomData<Message>,
+}
+
+impl<Message> SystemBus<Message> {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            pending: HashSet::new(),
+            _message: PhantomData,
+        }
+    }
+
+    pub fn next_id(&mut self) -> CorrelationId {
+        let id = CorrelationId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    // True while a request with `id` has been sent and its response not yet matched.
+    pub fn is_pending(&self, id: CorrelationId) -> bool {
+        self.pending.contains(&id)
+    }
+
+    pub fn request(
+        &mut self,
+        queue: &mut MessageQueue<BusMessage<Message>>,
+        id: CorrelationId,
+        message: Message,
+    ) {
+        self.pending.insert(id);
+        queue.push(BusMessage::Request(id, message));
+    }
+
+    pub fn respond(
+        queue: &mut MessageQueue<BusMessage<Message>>,
+        id: CorrelationId,
+        message: Message,
+    ) {
+        queue.push(BusMessage::Response(id, message));
+    }
+
+    // Takes the current-tick response to the request with `id`, if it has arrived.
+    pub fn match_response(
+        &mut self,
+        queue: &mut MessageQueue<BusMessage<Message>>,
+        id: CorrelationId,
+    ) -> Option<Message> {
+        let response = queue.take_first_matching(
+            |message| matches!(message, BusMessage::Response(response_id, _) if *response_id == id),
+        )?;
+        self.pending.remove(&id);
+        match response {
+            BusMessage::Response(_, message) => Some(message),
+            BusMessage::Request(..) => None,
+        }
+    }
+}
+
+impl<Message> Default for SystemBus<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    // Answers every request with the square of its value.
+    fn square_requests(queue: &mut MessageQueue<BusMessage<i32>>) {
+        let requests: Vec<_> = queue
+            .iter()
+            .filter_map(|message| match message {
+                BusMessage::Request(id, value) => Some((*id, *value)),
+                BusMessage::Response(..) => None,
+            })
+            .collect();
+        for (id, value) in requests {
+            SystemBus::respond(queue, id, value * value);
+        }
+    }
+
+    #[test]
+    fn test_request_response_across_ticks() {
+        let mut bus = SystemBus::new();
+        let mut queue = MessageQueue::new();
+
+        let first = bus.next_id();
+        let second = bus.next_id();
+        assert_ne!(first, second);
+        bus.request(&mut queue, first, 3);
+        bus.request(&mut queue, second, 4);
+        assert!(bus.is_pending(first));
+
+        queue.next_tick();
+        square_requests(&mut queue);
+        assert_eq!(bus.match_response(&mut queue, first), None);
+
+        queue.next_tick();
+        assert_eq!(bus.match_response(&mut queue, second), Some(16));
+        assert_eq!(bus.match_response(&mut queue, first), Some(9));
+        assert!(!bus.is_pending(first));
+        assert!(!bus.is_pending(second));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_match_response_ignores_requests() {
+        let mut bus = SystemBus::new();
+        let mut queue = MessageQueue::new();
+        let id = bus.next_id();
+        bus.request(&mut queue, id, 5);
+        queue.next_tick();
+
+        assert_eq!(bus.match_response(&mut queue, id), None);
+        assert!(bus.is_pending(id));
+        assert_eq!(queue.len(), 1);
+    }
+}
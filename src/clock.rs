@@ -0,0 +1,72 @@
+// src/clock.rs
+
+// The `clock.rs` module defines the `Clock` trait, the framework's only notion of time. The
+// core loop is driven purely by ticks, so anything that needs elapsed time (benchmarks, timing
+// budgets, timestamps) asks a `Clock` instead of reaching for a platform API. Embedded targets
+// implement it over a hardware timer, hosted programs over the system clock.
+
+// - ManualClock: A clock that only moves when told to, or by a fixed step each time it is
+//   read. It makes time-dependent code deterministic in tests and simulations.
+
+pub trait Clock {
+    // Milliseconds since an arbitrary, fixed starting point. Must never go backwards.
+    fn now_ms(&mut self) -> u64;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now_ms: u64,
+    step_ms: u64,
+}
+
+impl ManualClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            now_ms: start_ms,
+            step_ms: 0,
+        }
+    }
+
+    // A clock that advances by `step_ms` after every reading.
+    pub fn stepping(start_ms: u64, step_ms: u64) -> Self {
+        Self {
+            now_ms: start_ms,
+            step_ms,
+        }
+    }
+
+    pub fn advance(&mut self, ms: u64) {
+        self.now_ms += ms;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&mut self) -> u64 {
+        let now_ms = self.now_ms;
+        self.now_ms += self.step_ms;
+        now_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let mut clock = ManualClock::new(100);
+        assert_eq!(clock.now_ms(), 100);
+        assert_eq!(clock.now_ms(), 100);
+        clock.advance(5);
+        assert_eq!(clock.now_ms(), 105);
+    }
+
+    #[test]
+    fn test_stepping_clock() {
+        let mut clock = ManualClock::stepping(0, 10);
+        assert_eq!(clock.now_ms(), 0);
+        assert_eq!(clock.now_ms(), 10);
+        clock.advance(5);
+        assert_eq!(clock.now_ms(), 25);
+    }
+}
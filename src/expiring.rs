@@ -0,0 +1,94 @@
+// src/expiring.rs
+
+// The `expiring.rs` module adds tick-aligned expiry to the message queue, so a flight loop does
+// not act on commands that have become stale. As with priorities, an `ExpiringMessageQueue<M>`
+// is a `MessageQueue` of `Expiring<M>` values and works with any system written for it.
+
+// - Time To Live: Every message carries the number of tick transitions it may wait for
+//   delivery. Normally a message is delivered on the very next tick, but a message held back,
+//   for example by the batch window of `MessageQueue::batch_iter`, loses one unit of TTL for
+//   every tick it waits. A message whose TTL has reached zero is dropped instead of delivered.
+
+// - Single Delivery: Like any other message, an expiring message is delivered during exactly
+//   one tick. `next_tick_expiring` drops the expired messages before promoting the next tick
+//   and never puts delivered messages back on the queue. `run_expiring` in the `run` module
+//   drives a program with this tick transition instead of the plain `next_tick`.
+
+use crate::message_queue::MessageQueue;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expiring<Message> {
+    pub ttl: u32,
+    pub message: Message,
+}
+
+impl<Message> Expiring<Message> {
+    pub fn new(message: Message, ttl: u32) -> Self {
+        Self { ttl, message }
+    }
+}
+
+pub type ExpiringMessageQueue<Message> = MessageQueue<Expiring<Message>>;
+
+impl<Message> MessageQueue<Expiring<Message>> {
+    pub fn push_with_ttl(&mut self, message: Message, ttl: u32) {
+        self.push(Expiring::new(message, ttl));
+    }
+
+    pub fn next_tick_expiring(&mut self) {
+        self.next_tick_queue.retain(|expiring| 0 < expiring.ttl);
+        self.next_tick();
+        // Whatever was held back waits another tick.
+        for expiring in self.next_tick_queue.iter_mut() {
+            expiring.ttl = expiring.ttl.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn current(queue: &ExpiringMessageQueue<char>) -> Vec<char> {
+        queue.iter().map(|expiring| expiring.message).collect()
+    }
+
+    #[test]
+    fn test_short_lived_message_expires() {
+        let mut queue = ExpiringMessageQueue::new();
+        queue.batch_iter(1);
+        queue.push_with_ttl('a', 3);
+        queue.push_with_ttl('b', 1);
+        queue.push_with_ttl('c', 3);
+
+        queue.next_tick_expiring();
+        assert_eq!(current(&queue), ['a']);
+        // 'b' waited one tick, using up its TTL.
+        queue.next_tick_expiring();
+        assert_eq!(current(&queue), ['c']);
+        queue.next_tick_expiring();
+        assert!(current(&queue).is_empty());
+    }
+
+    #[test]
+    fn test_messages_are_delivered_once() {
+        let mut queue = ExpiringMessageQueue::new();
+        queue.push_with_ttl('a', 3);
+        queue.push_with_ttl('b', 1);
+
+        queue.next_tick_expiring();
+        assert_eq!(current(&queue), ['a', 'b']);
+        queue.next_tick_expiring();
+        assert!(current(&queue).is_empty());
+    }
+
+    #[test]
+    fn test_expired_message_is_not_delivered() {
+        let mut queue = ExpiringMessageQueue::new();
+        queue.push_with_ttl('a', 0);
+        queue.push_with_ttl('b', 1);
+        queue.next_tick_expiring();
+        assert_eq!(current(&queue), ['b']);
+    }
+}
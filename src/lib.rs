@@ -8,14 +8,28 @@
 // applications that require modularity and scalability, such as embedded systems or complex application logic.
 // 
 // Modules:
+// - expiring: Tick-aligned message expiry layered on the message queue through the `Expiring` wrapper.
 // - message_queue: Implements a message queue system that handles the asynchronous exchange of messages
 //   between different components of the application. This module is crucial for the non-blocking communication
 //   pattern that the framework facilitates.
 // - system: Defines the `System` trait, a fundamental concept in the framework that represents a modular unit
 //   of functionality. Each system can interact with others through the message queue and can alter the program's
 //   state.
+// - parallel: A `run` variant that updates systems with non-overlapping state access on separate threads,
+//   available with the `std` feature.
+// - priority: Message priorities layered on the message queue through the `Prioritized` wrapper.
+// - bus: Correlates requests with their responses across ticks.
+// - router: Routes each message type through its own queue to the systems subscribed to that type.
+// - rng: A deterministic pseudo random number generator whose state can be saved and restored for replay.
 // - run: Contains the primary runtime loop that drives the application. It coordinates the execution of different
 //   systems based on the program state and messages in the queue.
+// - clock: Defines the `Clock` trait used wherever elapsed time matters, along with a manual clock for tests.
+// - bench: A harness for measuring system throughput, available with the `testing` feature.
+// - scheduler: Orders named systems by their declared dependencies and renders the dependency graph.
+// - step_runner: Owns a running program and advances it one tick at a time, for callers that drive the loop
+//   themselves.
+// - systems: A collection of reusable, generic `System` implementations that cover common patterns such as
+//   message deduplication.
 //
 // Design Philosophy:
 // The Flight Brain Framework emphasizes a decoupled and event-driven architecture, allowing for highly modular 
@@ -34,7 +48,22 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(feature = "testing")]
+pub mod bench;
+pub mod bus;
+pub mod clock;
+pub mod expiring;
 pub mod message_queue;
+#[cfg(feature = "std")]
+pub mod parallel;
+pub mod priority;
+pub mod rng;
+pub mod router;
 pub mod run;
+pub mod scheduler;
+pub mod step_runner;
 pub mod system;
+pub mod systems;
@@ -21,6 +21,10 @@
 // - Queue Management: Messages for the next system tick are queued using the `push` method, and
 //   moving messages to the current tick's queue is handled by the `next_tick` method. This setup
 //   facilitates clear transitions between system ticks and simplifies message lifecycle management.
+//   The queue also counts the `next_tick` calls, so systems can tell which tick they are in.
+//   A queue created with `with_limit` holds at most that many messages for the next tick. Once
+//   the limit is reached, `try_push` hands a message back instead of queueing it, and every other
+//   way of queueing a message drops it and counts it in `rejected`.
 
 // - Testing: The included tests demonstrate the functionality of the message queue, such as message
 //   pushing, tick transition handling, and behavior with empty queues. These tests ensure the
@@ -32,12 +36,24 @@
 // working with this framework.
 
 extern crate alloc;
-use alloc::collections::VecDeque;
-use core::mem;
+use alloc::{
+    collections::{vec_deque::Drain, VecDeque},
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    hash::Hash,
+    mem::{self, Discriminant},
+};
+use hashbrown::{HashMap, HashSet};
 
 pub struct MessageQueue<T> {
-    current_tick_queue: VecDeque<T>,
-    next_tick_queue: VecDeque<T>,
+    pub(crate) current_tick_queue: VecDeque<T>,
+    pub(crate) next_tick_queue: VecDeque<T>,
+    pub(crate) tick: u64,
+    batch_window: Option<usize>,
+    limit: Option<usize>,
+    rejected: u64,
 }
 
 impl<T> Default for MessageQueue<T> {
@@ -51,30 +67,455 @@ impl<T> MessageQueue<T> {
         MessageQueue {
             current_tick_queue: VecDeque::new(),
             next_tick_queue: VecDeque::new(),
+            tick: 0,
+            batch_window: None,
+            limit: None,
+            rejected: 0,
         }
     }
 
+    // An empty queue that holds at most `limit` messages for the next tick, so a runaway system
+    // cannot exhaust the allocator. Room for all of them is allocated up front.
+    pub fn with_limit(limit: usize) -> Self {
+        let mut queue = Self::new();
+        queue.next_tick_queue.reserve(limit);
+        queue.limit = Some(limit);
+        queue
+    }
+
+    // The number of messages dropped so far because the next tick was full.
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    // How many more messages fit into the next tick.
+    fn room(&self) -> usize {
+        match self.limit {
+            Some(limit) => limit.saturating_sub(self.next_tick_queue.len()),
+            None => usize::MAX,
+        }
+    }
+
+    // Whether one more message fits into the next tick. If not, the caller drops the message
+    // and it is counted as rejected.
+    pub(crate) fn admit(&mut self) -> bool {
+        if 0 < self.room() {
+            true
+        } else {
+            self.rejected += 1;
+            false
+        }
+    }
+
+    // An empty queue whose tick counter starts at `tick`, for tests of tick-dependent systems.
+    pub fn with_tick(tick: u64) -> Self {
+        MessageQueue {
+            tick,
+            ..Self::new()
+        }
+    }
+
+    // The number of ticks started so far: zero before the first `next_tick`, one during the
+    // first tick, and so on.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // The number of messages in the current tick.
+    pub fn len(&self) -> usize {
+        self.current_tick_queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current_tick_queue.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.current_tick_queue.iter()
     }
 
+    // The first current-tick message, without consuming it.
+    pub fn peek(&self) -> Option<&T> {
+        self.current_tick_queue.front()
+    }
+
+    // The last current-tick message, without consuming it.
+    pub fn peek_back(&self) -> Option<&T> {
+        self.current_tick_queue.back()
+    }
+
+    // Yields at most `n` current-tick messages, for systems with a bounded budget per tick.
+    pub fn iter_take(&self, n: usize) -> impl Iterator<Item = &T> {
+        self.current_tick_queue.iter().take(n)
+    }
+
+    // Yields each current-tick message together with the one after it: `(a, b), (b, c)` for
+    // `[a, b, c]`.
+    pub fn pairs_current(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.current_tick_queue
+            .iter()
+            .zip(self.current_tick_queue.iter().skip(1))
+    }
+
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.current_tick_queue.iter_mut()
     }
 
+    // Yields mutable references to the current-tick messages matching `pred`, leaving the
+    // others alone.
+    pub fn iter_mut_filter<F: Fn(&T) -> bool>(&mut self, pred: F) -> impl Iterator<Item = &mut T> {
+        self.current_tick_queue
+            .iter_mut()
+            .filter(move |message| pred(message))
+    }
+
+    // The index of the first current-tick message matching `pred`.
+    pub fn position<F: FnMut(&T) -> bool>(&self, pred: F) -> Option<usize> {
+        self.current_tick_queue.iter().position(pred)
+    }
+
+    pub fn any_current<F: FnMut(&T) -> bool>(&self, f: F) -> bool {
+        self.current_tick_queue.iter().any(f)
+    }
+
+    // True for an empty current tick.
+    pub fn all_current<F: FnMut(&T) -> bool>(&self, f: F) -> bool {
+        self.current_tick_queue.iter().all(f)
+    }
+
+    // The first current-tick message matching `pred`.
+    pub fn first_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Option<&T> {
+        self.current_tick_queue.iter().find(|message| pred(message))
+    }
+
+    // The last current-tick message matching `pred`, e.g. the latest of several updates.
+    pub fn last_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Option<&T> {
+        self.current_tick_queue
+            .iter()
+            .rev()
+            .find(|message| pred(message))
+    }
+
+    // Rearranges the current-tick messages into one contiguous slice and returns it, so they
+    // can be sorted or reordered in place.
+    pub fn make_current_contiguous(&mut self) -> &mut [T] {
+        self.current_tick_queue.make_contiguous()
+    }
+
+    // Reduces the current-tick messages to a single value, front to back.
+    pub fn fold<B, F: FnMut(B, &T) -> B>(&self, init: B, f: F) -> B {
+        self.current_tick_queue.iter().fold(init, f)
+    }
+
+    // Removes and returns the first current-tick message, for systems that consume one message
+    // at a time.
+    pub fn pop_front_current(&mut self) -> Option<T> {
+        self.current_tick_queue.pop_front()
+    }
+
+    // Removes and returns the first current-tick message matching `pred`, leaving the others in
+    // place. Lets a system claim the one message it is responsible for.
+    pub fn take_first_matching<F: FnMut(&T) -> bool>(&mut self, pred: F) -> Option<T> {
+        let index = self.current_tick_queue.iter().position(pred)?;
+        self.current_tick_queue.remove(index)
+    }
+
+    // Removes every current-tick message matching `pred` and returns them in order, leaving the
+    // others in place. Takes a single pass over the tick, however many messages match.
+    pub fn take_all_matching<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> VecDeque<T> {
+        let mut taken = VecDeque::new();
+        let mut kept = VecDeque::with_capacity(self.current_tick_queue.len());
+        for message in self.current_tick_queue.drain(..) {
+            if pred(&message) {
+                taken.push_back(message);
+            } else {
+                kept.push_back(message);
+            }
+        }
+        self.current_tick_queue = kept;
+        taken
+    }
+
+    // Moves the current-tick messages out of the queue front to back, so a system can consume
+    // and transform them by value. Messages not yet yielded when the iterator is dropped are
+    // dropped too.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        self.current_tick_queue.drain(..)
+    }
+
+    // Removes and returns the leading current-tick messages while `pred` holds, stopping at the
+    // first message that does not match, e.g. to consume one framed group of messages.
+    pub fn drain_current_while<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<T> {
+        let count = self
+            .current_tick_queue
+            .iter()
+            .position(|message| !pred(message))
+            .unwrap_or(self.current_tick_queue.len());
+        self.current_tick_queue.drain(..count).collect()
+    }
+
+    // Keeps only the current-tick messages for which `f` returns true, in their original
+    // order, e.g. to suppress messages that became obsolete earlier in the tick.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.current_tick_queue.retain(f);
+    }
+
+    pub fn map_current_in_place<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.current_tick_queue.iter_mut().for_each(f);
+    }
+
+    // Iterates over the current-tick messages followed by the messages queued for the next tick.
+    pub fn iter_all(&self) -> impl Iterator<Item = &T> {
+        self.current_tick_queue
+            .iter()
+            .chain(self.next_tick_queue.iter())
+    }
+
+    // Counts the current-tick messages per enum variant, without a hand-written key function.
+    pub fn count_by_discriminant(&self) -> HashMap<Discriminant<T>, usize> {
+        let mut counts = HashMap::new();
+        for message in self.current_tick_queue.iter() {
+            *counts.entry(mem::discriminant(message)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // Groups the current-tick messages by the key `key_of` assigns them. Within a group the
+    // messages keep their queue order.
+    pub fn group_by<K: Eq + Hash, F: Fn(&T) -> K>(&self, key_of: F) -> HashMap<K, Vec<&T>> {
+        let mut groups: HashMap<K, Vec<&T>> = HashMap::new();
+        for message in self.current_tick_queue.iter() {
+            groups.entry(key_of(message)).or_default().push(message);
+        }
+        groups
+    }
+
+    // Reserves room for at least `additional` more messages in the next tick.
+    pub fn reserve(&mut self, additional: usize) {
+        self.next_tick_queue.reserve(additional);
+    }
+
+    // Releases memory held by the next-tick buffer, keeping room for at least `min_capacity`
+    // messages, e.g. after a burst.
+    pub fn shrink_next_to(&mut self, min_capacity: usize) {
+        self.next_tick_queue.shrink_to(min_capacity);
+    }
+
+    // Drops the message if the next tick is at its limit, see `with_limit`.
     pub fn push(&mut self, message: T) {
-        self.next_tick_queue.push_back(message);
+        if self.admit() {
+            self.next_tick_queue.push_back(message);
+        }
+    }
+
+    // Same as `push`, but a message that does not fit is handed back untouched instead of being
+    // dropped. Always succeeds on a queue without a limit.
+    pub fn try_push(&mut self, message: T) -> Result<(), T> {
+        if 0 < self.room() {
+            self.next_tick_queue.push_back(message);
+            Ok(())
+        } else {
+            Err(message)
+        }
+    }
+
+    // Queues an urgent message ahead of everything already pushed for the next tick. Messages
+    // pushed to the front later still come before it.
+    pub fn push_front(&mut self, message: T) {
+        if self.admit() {
+            self.next_tick_queue.push_front(message);
+        }
+    }
+
+    // Schedules `message` at position `index` among the messages already queued for the next
+    // tick. Panics if `index` is greater than the number of queued messages.
+    pub fn insert_next(&mut self, index: usize, message: T) {
+        assert!(index <= self.next_tick_queue.len(), "Index out of range.");
+        if self.admit() {
+            self.next_tick_queue.insert(index, message);
+        }
+    }
+
+    // Moves the first next-tick message matching `pred` to the front of the next tick, keeping
+    // the relative order of the others. Returns whether a matching message was found.
+    pub fn move_to_front_next<F: FnMut(&T) -> bool>(&mut self, pred: F) -> bool {
+        match self.next_tick_queue.iter().position(pred) {
+            Some(index) => {
+                let message = self
+                    .next_tick_queue
+                    .remove(index)
+                    .expect("Message expected.");
+                self.next_tick_queue.push_front(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Pushes `message` unless a message with the same key is already queued for the next
+    // tick, in which case `merge` folds the new message into the queued one.
+    pub fn upsert<K: Eq, F, G>(&mut self, key_of: F, merge: G, message: T)
+    where
+        F: Fn(&T) -> K,
+        G: FnOnce(&mut T, T),
+    {
+        let key = key_of(&message);
+        match self
+            .next_tick_queue
+            .iter_mut()
+            .find(|queued| key_of(queued) == key)
+        {
+            Some(queued) => merge(queued, message),
+            None => self.push(message),
+        }
+    }
+
+    // Passes every next-tick message through `f` by value, keeping the `Some` results in order
+    // and dropping the messages mapped to `None`.
+    pub fn retain_map_next<F: FnMut(T) -> Option<T>>(&mut self, f: F) {
+        let retained = self.next_tick_queue.drain(..).filter_map(f).collect();
+        self.next_tick_queue = retained;
+    }
+
+    // The number of messages queued for the next tick so far.
+    pub fn next_len(&self) -> usize {
+        self.next_tick_queue.len()
+    }
+
+    // Moves the next-tick messages matching `pred` into a new queue, where they are queued for
+    // its next tick in their original order. Returns the new queue and the number of messages
+    // moved.
+    pub fn split_next<F: Fn(&T) -> bool>(&mut self, pred: F) -> (MessageQueue<T>, usize) {
+        let mut split = MessageQueue::new();
+        let mut kept = VecDeque::with_capacity(self.next_tick_queue.len());
+        for message in self.next_tick_queue.drain(..) {
+            if pred(&message) {
+                split.next_tick_queue.push_back(message);
+            } else {
+                kept.push_back(message);
+            }
+        }
+        self.next_tick_queue = kept;
+        let moved = split.next_tick_queue.len();
+        (split, moved)
+    }
+
+    // Keeps the first `n` next-tick messages and drops the rest.
+    pub fn retain_first_n(&mut self, n: usize) {
+        self.next_tick_queue.truncate(n);
+    }
+
+    // Keeps the last `n` next-tick messages and drops the rest.
+    pub fn retain_last_n(&mut self, n: usize) {
+        let excess = self.next_tick_queue.len().saturating_sub(n);
+        self.next_tick_queue.drain(..excess);
+    }
+
+    // Sorts the next-tick messages with `compare`. The sort is stable, so messages that compare
+    // equal keep the order they were pushed in.
+    pub fn sort_next_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.next_tick_queue.make_contiguous().sort_by(compare);
+    }
+
+    // Keeps only the first next-tick message for each key, wherever the duplicates are.
+    pub fn dedup_by_key<K: Eq + Hash, F: FnMut(&T) -> K>(&mut self, mut key_of: F) {
+        let mut seen = HashSet::new();
+        self.next_tick_queue
+            .retain(|message| seen.insert(key_of(message)));
+    }
+
+    // Iterates over the messages queued for the next tick so far, e.g. to avoid pushing a
+    // message that is already pending.
+    pub fn iter_next(&self) -> impl Iterator<Item = &T> {
+        self.next_tick_queue.iter()
+    }
+
+    pub fn peek_next(&self) -> Option<&T> {
+        self.next_tick_queue.front()
+    }
+
+    pub fn peek_next_back(&self) -> Option<&T> {
+        self.next_tick_queue.back()
+    }
+
+    // Iterates over the next-tick messages in slices of at most `size` messages. Chunks never
+    // span the point where the underlying ring buffer wraps around, so a chunk may be shorter
+    // than `size` even when more messages follow. Panics if `size` is zero.
+    pub fn chunks_next(&self, size: usize) -> impl Iterator<Item = &[T]> {
+        let (front, back) = self.next_tick_queue.as_slices();
+        front.chunks(size).chain(back.chunks(size))
+    }
+
+    // Moves the current-tick messages of `other` to the back of this queue's next tick,
+    // leaving `other`'s current tick empty.
+    pub fn extend_next_from_current(&mut self, other: &mut MessageQueue<T>) {
+        for message in other.current_tick_queue.drain(..) {
+            self.push(message);
+        }
+    }
+
+    // Moves every message out of the queue, current-tick messages first, leaving both ticks
+    // empty.
+    pub fn drain_all(&mut self) -> Vec<T> {
+        let mut messages: Vec<T> = self.current_tick_queue.drain(..).collect();
+        messages.extend(self.next_tick_queue.drain(..));
+        messages
+    }
+
+    // Discards every message in both ticks, e.g. to restart the message pipeline after an
+    // abort. The tick counter keeps counting.
+    pub fn clear(&mut self) {
+        self.current_tick_queue.clear();
+        self.next_tick_queue.clear();
+    }
+
+    // Discards the messages queued for the next tick, leaving the current tick alone.
+    pub fn clear_next(&mut self) {
+        self.next_tick_queue.clear();
+    }
+
+    // Consumes `other` and appends its next-tick messages, converted by `f`, to this queue's
+    // next tick. Messages in `other`'s current tick are dropped.
+    pub fn merge_map_next<U, F: FnMut(U) -> T>(&mut self, other: MessageQueue<U>, f: F) {
+        for message in other.next_tick_queue.into_iter().map(f) {
+            self.push(message);
+        }
+    }
+
+    // Caps every tick at `window` messages. From now on `next_tick` promotes at most `window`
+    // of the queued messages and keeps the rest at the front of the next tick, ahead of newly
+    // pushed messages, spreading a burst evenly over the following ticks. Panics if `window` is
+    // zero.
+    pub fn batch_iter(&mut self, window: usize) {
+        assert!(0 < window, "Batch window must hold at least one message.");
+        self.batch_window = Some(window);
+    }
+
+    // Exchanges the next-tick messages of this queue with those of `other`. The current-tick
+    // messages of both queues are left alone.
+    pub fn swap_buffers_with(&mut self, other: &mut MessageQueue<T>) {
+        mem::swap(&mut self.next_tick_queue, &mut other.next_tick_queue);
     }
 
     pub fn next_tick(&mut self) {
         mem::swap(&mut self.current_tick_queue, &mut self.next_tick_queue);
         self.next_tick_queue.clear();
+        if let Some(window) = self.batch_window {
+            if window < self.current_tick_queue.len() {
+                self.next_tick_queue = self.current_tick_queue.split_off(window);
+            }
+        }
+        self.tick += 1;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{
+        format,
+        string::{String, ToString},
+    };
 
     #[test]
     fn test_push_and_iter() {
@@ -119,4 +560,833 @@ mod tests {
         queue.next_tick();
         assert!(queue.iter().next().is_none());
     }
+
+    #[test]
+    fn test_peek_next_empty() {
+        let queue: MessageQueue<i32> = MessageQueue::new();
+        assert_eq!(queue.peek_next(), None);
+        assert_eq!(queue.peek_next_back(), None);
+    }
+
+    #[test]
+    fn test_peek_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+
+        assert_eq!(queue.peek_next(), Some(&1));
+        assert_eq!(queue.peek_next_back(), Some(&3));
+        assert_eq!(queue.next_tick_queue.len(), 3);
+
+        queue.next_tick();
+        assert_eq!(queue.peek_next(), None);
+        assert_eq!(queue.peek_next_back(), None);
+    }
+
+    #[test]
+    fn test_upsert_merges_by_key() {
+        let mut queue: MessageQueue<(char, i32)> = MessageQueue::new();
+        let key_of = |message: &(char, i32)| message.0;
+        let merge = |queued: &mut (char, i32), message: (char, i32)| queued.1 += message.1;
+
+        queue.upsert(key_of, merge, ('a', 1));
+        queue.upsert(key_of, merge, ('b', 10));
+        queue.upsert(key_of, merge, ('a', 2));
+        queue.next_tick();
+
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&('a', 3)));
+        assert_eq!(iter.next(), Some(&('b', 10)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_chunks_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in 1..=5 {
+            queue.push(value);
+        }
+
+        let mut chunks = queue.chunks_next(2);
+        assert_eq!(chunks.next(), Some(&[1, 2][..]));
+        assert_eq!(chunks.next(), Some(&[3, 4][..]));
+        assert_eq!(chunks.next(), Some(&[5][..]));
+        assert_eq!(chunks.next(), None);
+    }
+
+    #[test]
+    fn test_chunks_next_wrapped() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.next_tick_queue.push_back(3);
+        queue.next_tick_queue.push_back(4);
+        queue.next_tick_queue.push_front(2);
+        queue.next_tick_queue.push_front(1);
+
+        let mut values = Vec::new();
+        for chunk in queue.chunks_next(2) {
+            assert!(!chunk.is_empty() && chunk.len() <= 2);
+            values.extend_from_slice(chunk);
+        }
+        assert_eq!(values, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_iter_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.next_tick();
+        assert_eq!(queue.iter_next().next(), None);
+
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.iter_next().copied().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1]);
+
+        queue.next_tick();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(queue.iter_next().next(), None);
+    }
+
+    #[test]
+    fn test_try_push_beyond_limit() {
+        let mut queue: MessageQueue<String> = MessageQueue::with_limit(2);
+        assert_eq!(queue.try_push("one".to_string()), Ok(()));
+        assert_eq!(queue.try_push("two".to_string()), Ok(()));
+        assert_eq!(
+            queue.try_push("three".to_string()),
+            Err("three".to_string())
+        );
+        assert_eq!(queue.next_len(), 2);
+        assert_eq!(queue.rejected(), 0);
+    }
+
+    #[test]
+    fn test_try_push_after_next_tick() {
+        let mut queue: MessageQueue<i32> = MessageQueue::with_limit(2);
+        assert_eq!(queue.try_push(1), Ok(()));
+        assert_eq!(queue.try_push(2), Ok(()));
+        assert_eq!(queue.try_push(3), Err(3));
+
+        queue.next_tick();
+        assert_eq!(queue.try_push(4), Ok(()));
+        assert_eq!(queue.try_push(5), Ok(()));
+        assert_eq!(queue.try_push(6), Err(6));
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 2]);
+        queue.next_tick();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [4, 5]);
+    }
+
+    #[test]
+    fn test_try_push_without_limit() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in 0..100 {
+            assert_eq!(queue.try_push(value), Ok(()));
+        }
+        assert_eq!(queue.next_len(), 100);
+    }
+
+    #[test]
+    fn test_limit_applies_to_every_insertion() {
+        let mut queue: MessageQueue<i32> = MessageQueue::with_limit(3);
+        queue.push(1);
+        queue.push_front(0);
+        queue.insert_next(1, 5);
+        queue.push(2);
+        queue.push_front(-1);
+        queue.insert_next(0, 7);
+        queue.upsert(|message| *message, |_, _| (), 8);
+        assert_eq!(queue.iter_next().copied().collect::<Vec<_>>(), [0, 5, 1]);
+        assert_eq!(queue.rejected(), 4);
+
+        let mut other = MessageQueue::new();
+        other.push(10);
+        other.push(11);
+        other.next_tick();
+        queue.next_tick();
+        queue.push(20);
+        queue.extend_next_from_current(&mut other);
+        assert!(other.is_empty());
+        let mut source = MessageQueue::new();
+        source.push(30);
+        queue.merge_map_next(source, |message: i32| message);
+        assert_eq!(queue.iter_next().copied().collect::<Vec<_>>(), [20, 10, 11]);
+        assert_eq!(queue.rejected(), 5);
+    }
+
+    #[test]
+    fn test_push_front() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        queue.push_front(0);
+        queue.push(4);
+        queue.next_tick();
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_insert_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(3);
+        queue.insert_next(1, 2);
+        queue.insert_next(3, 4);
+        queue.next_tick();
+
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_next_out_of_range() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.insert_next(2, 2);
+    }
+
+    #[test]
+    fn test_iter_all() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.push(3);
+
+        let mut iter = queue.iter_all();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(queue.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_drain_current_while() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in [1, 2, 3, 0, 4, 5] {
+            queue.push(value);
+        }
+        queue.next_tick();
+
+        assert_eq!(queue.drain_current_while(|message| 0 < *message), [1, 2, 3]);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [0, 4, 5]);
+        assert_eq!(queue.drain_current_while(|message| 0 < *message), []);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in 1..=5 {
+            queue.push(value);
+        }
+        queue.next_tick();
+
+        queue.retain(|message| 0 == message % 2);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [2, 4]);
+    }
+
+    #[test]
+    fn test_map_current_in_place() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.push(10);
+
+        queue.map_current_in_place(|message| *message += 1);
+
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(queue.peek_next(), Some(&10));
+    }
+
+    #[test]
+    fn test_pop_front_current() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.push(3);
+
+        assert_eq!(queue.pop_front_current(), Some(1));
+        assert_eq!(queue.iter().count(), 1);
+        assert_eq!(queue.pop_front_current(), Some(2));
+        assert_eq!(queue.pop_front_current(), None);
+        assert_eq!(queue.peek_next(), Some(&3));
+    }
+
+    #[test]
+    fn test_take_first_matching() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in [1, 4, 6, 7] {
+            queue.push(value);
+        }
+        queue.next_tick();
+
+        assert_eq!(
+            queue.take_first_matching(|message| 0 == message % 2),
+            Some(4)
+        );
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 6, 7]);
+        assert_eq!(queue.take_first_matching(|message| 10 < *message), None);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_take_all_matching() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in [1, 4, 6, 7, 8] {
+            queue.push(value);
+        }
+        queue.next_tick();
+
+        assert_eq!(
+            queue.take_all_matching(|message| 0 == message % 2),
+            [4, 6, 8]
+        );
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 7]);
+        assert!(queue.take_all_matching(|message| 10 < *message).is_empty());
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_retain_map_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for value in 1..=5 {
+            queue.push(value);
+        }
+
+        queue.retain_map_next(|message| {
+            if 0 == message % 2 {
+                None
+            } else {
+                Some(message * 10)
+            }
+        });
+        queue.next_tick();
+
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&10));
+        assert_eq!(iter.next(), Some(&30));
+        assert_eq!(iter.next(), Some(&50));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_count_by_discriminant() {
+        #[allow(dead_code)]
+        enum TestMessage {
+            Command(i32),
+            Log(&'static str),
+            Shutdown,
+        }
+
+        let mut queue = MessageQueue::new();
+        queue.push(TestMessage::Command(1));
+        queue.push(TestMessage::Log("a"));
+        queue.push(TestMessage::Command(2));
+        queue.push(TestMessage::Command(3));
+        queue.push(TestMessage::Log("b"));
+        queue.next_tick();
+
+        let counts = queue.count_by_discriminant();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(
+            counts.get(&mem::discriminant(&TestMessage::Command(0))),
+            Some(&3)
+        );
+        assert_eq!(
+            counts.get(&mem::discriminant(&TestMessage::Log(""))),
+            Some(&2)
+        );
+        assert_eq!(counts.get(&mem::discriminant(&TestMessage::Shutdown)), None);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.reserve(10);
+        assert!(11 <= queue.next_tick_queue.capacity());
+    }
+
+    #[test]
+    fn test_extend_next_from_current() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        let mut other: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        other.push(2);
+        other.push(3);
+        other.next_tick();
+        other.push(4);
+
+        queue.extend_next_from_current(&mut other);
+
+        assert_eq!(other.iter().next(), None);
+        assert_eq!(other.peek_next(), Some(&4));
+        queue.next_tick();
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_position() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(5);
+        queue.push(7);
+        queue.push(7);
+        queue.next_tick();
+        queue.push(9);
+
+        assert_eq!(queue.position(|message| 7 == *message), Some(1));
+        assert_eq!(queue.position(|message| 9 == *message), None);
+    }
+
+    #[test]
+    fn test_move_to_front_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        queue.push(3);
+
+        assert!(queue.move_to_front_next(|message| 3 == *message));
+        assert!(!queue.move_to_front_next(|message| 4 == *message));
+        queue.next_tick();
+
+        let values: Vec<i32> = queue.iter().copied().collect();
+        assert_eq!(values, [3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_swap_buffers_with() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        let mut other: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.next_tick();
+        queue.push(2);
+        other.push(3);
+        other.next_tick();
+        other.push(4);
+        other.push(5);
+
+        queue.swap_buffers_with(&mut other);
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1]);
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), [3]);
+        queue.next_tick();
+        other.next_tick();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        queue.next_tick();
+        queue.push(4);
+
+        assert_eq!(queue.drain().collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek_next(), Some(&4));
+    }
+
+    #[test]
+    fn test_drain_all() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.push(3);
+
+        assert_eq!(queue.drain_all(), [1, 2, 3]);
+        assert_eq!(queue.iter_all().next(), None);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.next_tick();
+        queue.push(2);
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.next_len(), 0);
+        assert_eq!(queue.tick(), 1);
+    }
+
+    #[test]
+    fn test_clear_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.next_tick();
+        queue.push(2);
+
+        queue.clear_next();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1]);
+        assert_eq!(queue.next_len(), 0);
+    }
+
+    #[test]
+    fn test_make_current_contiguous() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(3);
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.current_tick_queue.push_front(4);
+
+        queue.make_current_contiguous().sort();
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_iter_take() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        queue.next_tick();
+
+        assert_eq!(queue.iter_take(2).copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(queue.iter_take(5).count(), 3);
+        assert_eq!(queue.iter_take(0).next(), None);
+    }
+
+    #[test]
+    fn test_merge_map_next() {
+        let mut queue: MessageQueue<String> = MessageQueue::new();
+        let mut other: MessageQueue<i32> = MessageQueue::new();
+        queue.push("zero".to_string());
+        other.push(1);
+        other.next_tick();
+        other.push(2);
+        other.push(3);
+
+        queue.merge_map_next(other, |value| format!("{}", value));
+        queue.next_tick();
+
+        assert_eq!(queue.iter().collect::<Vec<_>>(), ["zero", "2", "3"]);
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.next_tick();
+        queue.push(3);
+        queue.push(4);
+
+        assert_eq!(queue.fold(0, |sum, message| sum + message), 3);
+        queue.next_tick();
+        assert_eq!(queue.fold(0, |sum, message| sum + message), 7);
+    }
+
+    #[test]
+    fn test_group_by() {
+        let mut queue: MessageQueue<(char, i32)> = MessageQueue::new();
+        queue.push(('x', 1));
+        queue.push(('y', 2));
+        queue.push(('x', 3));
+        queue.next_tick();
+        queue.push(('z', 4));
+
+        let groups = queue.group_by(|(variable, _)| *variable);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&'x'], [&('x', 1), &('x', 3)]);
+        assert_eq!(groups[&'y'], [&('y', 2)]);
+        assert!(!groups.contains_key(&'z'));
+    }
+
+    #[test]
+    fn test_next_len() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.next_tick();
+        assert_eq!(queue.next_len(), 0);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.next_len(), 2);
+    }
+
+    #[test]
+    fn test_first_where_and_last_where() {
+        let mut queue: MessageQueue<(char, i32)> = MessageQueue::new();
+        queue.push(('s', 1));
+        queue.push(('g', 2));
+        queue.push(('s', 3));
+        queue.next_tick();
+        queue.push(('s', 4));
+
+        assert_eq!(queue.first_where(|(kind, _)| 's' == *kind), Some(&('s', 1)));
+        assert_eq!(queue.last_where(|(kind, _)| 's' == *kind), Some(&('s', 3)));
+        assert_eq!(queue.last_where(|(kind, _)| 'x' == *kind), None);
+    }
+
+    #[test]
+    fn test_any_current_and_all_current() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(2);
+        queue.push(4);
+        queue.next_tick();
+        queue.push(5);
+
+        assert!(queue.any_current(|message| 4 == *message));
+        assert!(!queue.any_current(|message| 5 == *message));
+        assert!(queue.all_current(|message| message % 2 == 0));
+        assert!(!queue.all_current(|message| 2 == *message));
+    }
+
+    #[test]
+    fn test_split_next() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(-2);
+        queue.push(3);
+        queue.push(-4);
+
+        let (mut negative, moved) = queue.split_next(|message| *message < 0);
+
+        assert_eq!(moved, 2);
+        negative.next_tick();
+        assert_eq!(negative.iter().copied().collect::<Vec<_>>(), [-2, -4]);
+        queue.next_tick();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    fn test_pairs_current() {
+        let mut queue: MessageQueue<char> = MessageQueue::new();
+        queue.push('a');
+        queue.push('b');
+        queue.push('c');
+        queue.next_tick();
+
+        assert_eq!(
+            queue.pairs_current().collect::<Vec<_>>(),
+            [(&'a', &'b'), (&'b', &'c')]
+        );
+        queue.next_tick();
+        assert_eq!(queue.pairs_current().next(), None);
+    }
+
+    #[test]
+    fn test_shrink_next_to() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.reserve(1000);
+        queue.push(1);
+        queue.push(2);
+
+        queue.shrink_next_to(16);
+
+        let capacity = queue.next_tick_queue.capacity();
+        assert!((16..1000).contains(&capacity));
+        assert_eq!(queue.next_len(), 2);
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        assert_eq!(queue.tick(), 0);
+        queue.next_tick();
+        queue.next_tick();
+        assert_eq!(queue.tick(), 2);
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let mut queue: MessageQueue<(char, i32)> = MessageQueue::new();
+        queue.push(('x', 1));
+        queue.push(('y', 2));
+        queue.push(('x', 3));
+        queue.push(('z', 4));
+        queue.push(('y', 5));
+
+        queue.dedup_by_key(|(variable, _)| *variable);
+        queue.next_tick();
+
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [('x', 1), ('y', 2), ('z', 4)]
+        );
+    }
+
+    #[test]
+    fn test_with_tick() {
+        let mut queue: MessageQueue<i32> = MessageQueue::with_tick(10_000);
+        assert_eq!(queue.tick(), 10_000);
+        queue.next_tick();
+        assert_eq!(queue.tick(), 10_001);
+    }
+
+    #[test]
+    fn test_batch_iter() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.batch_iter(3);
+        for message in 0..10 {
+            queue.push(message);
+        }
+
+        let mut ticks = Vec::new();
+        for _ in 0..5 {
+            queue.next_tick();
+            ticks.push(queue.iter().copied().collect::<Vec<_>>());
+        }
+
+        assert_eq!(
+            ticks,
+            [
+                Vec::from([0, 1, 2]),
+                Vec::from([3, 4, 5]),
+                Vec::from([6, 7, 8]),
+                Vec::from([9]),
+                Vec::new(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batch_iter_deferred_before_new() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.batch_iter(2);
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        queue.next_tick();
+        queue.push(4);
+        queue.push(5);
+        queue.next_tick();
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(queue.peek_next(), Some(&5));
+    }
+
+    #[test]
+    fn test_iter_mut_filter() {
+        let mut queue: MessageQueue<(char, i32)> = MessageQueue::new();
+        queue.push(('c', 1));
+        queue.push(('l', 2));
+        queue.push(('c', 3));
+        queue.next_tick();
+
+        for (_, value) in queue.iter_mut_filter(|(kind, _)| 'c' == *kind) {
+            *value *= 10;
+        }
+
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [('c', 10), ('l', 2), ('c', 30)]
+        );
+    }
+
+    #[test]
+    fn test_retain_first_n() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for message in 1..=5 {
+            queue.push(message);
+        }
+
+        queue.retain_first_n(2);
+        queue.next_tick();
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn test_retain_last_n() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        for message in 1..=5 {
+            queue.push(message);
+        }
+
+        queue.retain_last_n(2);
+        assert_eq!(queue.next_len(), 2);
+        queue.retain_last_n(10);
+        queue.next_tick();
+
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [4, 5]);
+    }
+
+    #[test]
+    fn test_len_and_is_empty() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+
+        // Messages for the next tick do not count until it starts.
+        queue.push(1);
+        assert!(queue.is_empty());
+        queue.next_tick();
+        assert_eq!(queue.len(), 1);
+        assert!(!queue.is_empty());
+
+        queue.push(2);
+        queue.push(3);
+        queue.next_tick();
+        assert_eq!(queue.len(), 2);
+        assert!(!queue.is_empty());
+
+        queue.next_tick();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_sort_next_by() {
+        let mut queue: MessageQueue<(u8, char)> = MessageQueue::new();
+        queue.push((2, 'a'));
+        queue.push((1, 'b'));
+        queue.push((2, 'c'));
+        queue.push((0, 'd'));
+
+        queue.sort_next_by(|left, right| left.0.cmp(&right.0));
+        queue.next_tick();
+
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [(0, 'd'), (1, 'b'), (2, 'a'), (2, 'c')]
+        );
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut queue: MessageQueue<i32> = MessageQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.peek(), None);
+        assert_eq!(queue.peek_back(), None);
+
+        queue.next_tick();
+        queue.push(4);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.peek_back(), Some(&3));
+        assert_eq!(queue.len(), 3);
+
+        queue.next_tick();
+        assert_eq!(queue.peek(), Some(&4));
+        assert_eq!(queue.peek_back(), Some(&4));
+    }
 }
@@ -0,0 +1,251 @@
+// src/parallel.rs
+
+// The `parallel.rs` module provides `run_parallel`, a variant of the `run` loop for multicore
+// `std` hosts. It is only available with the `std` feature, since it relies on scoped threads.
+
+// - Shared State: Systems updated concurrently cannot all hold `&mut ProgramState`, so
+//   `ParallelSystem` receives the program state by shared reference. State that such systems
+//   change lives behind atomics or locks, which is why the state must be `Sync`.
+
+// - Declared Access: Each system declares which parts of the state it reads and writes with a
+//   `StateAccess`. The parts are numbered by the program, one bit each. Consecutive systems
+//   whose access does not conflict form a batch and are updated on separate threads; a system
+//   that conflicts with the batch so far starts the next batch, so conflicting systems still
+//   run one after the other, in order.
+
+// - Messages: Every system sees the whole current tick. Outgoing messages are collected per
+//   system and pushed for the next tick in system order once the tick is over, so the result
+//   does not depend on thread timing.
+
+use crate::message_queue::MessageQueue;
+use alloc::{boxed::Box, vec, vec::Vec};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateAccess {
+    reads: u64,
+    writes: u64,
+}
+
+impl StateAccess {
+    pub const NONE: StateAccess = StateAccess {
+        reads: 0,
+        writes: 0,
+    };
+    // Conflicts with every other system, forcing sequential execution.
+    pub const EXCLUSIVE: StateAccess = StateAccess {
+        reads: u64::MAX,
+        writes: u64::MAX,
+    };
+
+    // Panics if `part` is 64 or more. A wrapped bit could make conflicting systems look
+    // disjoint and let them run on separate threads.
+    pub fn reading(self, part: u32) -> Self {
+        assert!(part < u64::BITS, "State part out of range.");
+        Self {
+            reads: self.reads | 1 << part,
+            ..self
+        }
+    }
+
+    // Panics if `part` is 64 or more, like `reading`.
+    pub fn writing(self, part: u32) -> Self {
+        assert!(part < u64::BITS, "State part out of range.");
+        Self {
+            writes: self.writes | 1 << part,
+            ..self
+        }
+    }
+
+    // Two systems conflict when either writes a part the other reads or writes.
+    pub fn conflicts_with(&self, other: &StateAccess) -> bool {
+        0 != self.writes & (other.reads | other.writes) || 0 != other.writes & self.reads
+    }
+}
+
+pub trait ParallelSystem<ProgramState, Message>: Send {
+    fn update(
+        &mut self,
+        program_state: &ProgramState,
+        messages: &MessageQueue<Message>,
+        outgoing: &mut Vec<Message>,
+    );
+
+    fn accesses(&self) -> StateAccess {
+        StateAccess::EXCLUSIVE
+    }
+}
+
+pub fn run_parallel<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+) where
+    ProgramState: Sync,
+    Message: Send + Sync,
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn ParallelSystem<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn ParallelSystem<ProgramState, Message>>>,
+{
+    let mut systems = update(&mut program_state, &mut message_queue, vec![]);
+
+    while !systems.is_empty() {
+        message_queue.next_tick();
+        let mut outgoing: Vec<Vec<Message>> = systems.iter().map(|_| Vec::new()).collect();
+        let mut remaining = &mut systems[..];
+        let mut remaining_outgoing = &mut outgoing[..];
+
+        while !remaining.is_empty() {
+            let batch_len = batch_len(remaining);
+            let (batch, rest) = remaining.split_at_mut(batch_len);
+            let (batch_outgoing, rest_outgoing) = remaining_outgoing.split_at_mut(batch_len);
+            update_batch(batch, batch_outgoing, &program_state, &message_queue);
+            remaining = rest;
+            remaining_outgoing = rest_outgoing;
+        }
+
+        for message in outgoing.into_iter().flatten() {
+            message_queue.push(message);
+        }
+        systems = update(&mut program_state, &mut message_queue, systems);
+    }
+}
+
+// The number of leading systems that can be updated together without conflicts.
+fn batch_len<ProgramState, Message>(
+    systems: &[Box<dyn ParallelSystem<ProgramState, Message>>],
+) -> usize {
+    let mut accesses: Vec<StateAccess> = Vec::new();
+    for system in systems {
+        let access = system.accesses();
+        if accesses.iter().any(|other| access.conflicts_with(other)) {
+            break;
+        }
+        accesses.push(access);
+    }
+    accesses.len().max(1)
+}
+
+fn update_batch<ProgramState, Message>(
+    batch: &mut [Box<dyn ParallelSystem<ProgramState, Message>>],
+    outgoing: &mut [Vec<Message>],
+    program_state: &ProgramState,
+    message_queue: &MessageQueue<Message>,
+) where
+    ProgramState: Sync,
+    Message: Send + Sync,
+{
+    if let [system] = batch {
+        system.update(program_state, message_queue, &mut outgoing[0]);
+        return;
+    }
+    thread::scope(|scope| {
+        for (system, outgoing) in batch.iter_mut().zip(outgoing.iter_mut()) {
+            scope.spawn(move || system.update(program_state, message_queue, outgoing));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    const LEFT: u32 = 0;
+    const RIGHT: u32 = 1;
+
+    struct TestProgramState {
+        left: AtomicU64,
+        right: AtomicU64,
+    }
+
+    // Adds every message to its side of the state and pushes the message back, halved.
+    struct SumSystem {
+        part: u32,
+    }
+
+    impl ParallelSystem<TestProgramState, u64> for SumSystem {
+        fn update(
+            &mut self,
+            program_state: &TestProgramState,
+            messages: &MessageQueue<u64>,
+            outgoing: &mut Vec<u64>,
+        ) {
+            let side = match self.part {
+                LEFT => &program_state.left,
+                _ => &program_state.right,
+            };
+            for message in messages.iter() {
+                side.fetch_add(*message, Ordering::SeqCst);
+                if 1 < *message {
+                    outgoing.push(message / 2);
+                }
+            }
+        }
+
+        fn accesses(&self) -> StateAccess {
+            StateAccess::NONE.writing(self.part)
+        }
+    }
+
+    #[test]
+    fn test_state_access_conflicts() {
+        let left = StateAccess::NONE.writing(LEFT);
+        assert!(!left.conflicts_with(&StateAccess::NONE.writing(RIGHT)));
+        assert!(left.conflicts_with(&StateAccess::NONE.reading(LEFT)));
+        assert!(StateAccess::NONE.reading(LEFT).conflicts_with(&left));
+        assert!(!StateAccess::NONE
+            .reading(LEFT)
+            .conflicts_with(&StateAccess::NONE.reading(LEFT)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_state_access_reading_out_of_range() {
+        StateAccess::NONE.reading(64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_state_access_writing_out_of_range() {
+        StateAccess::NONE.writing(64);
+    }
+
+    #[test]
+    fn test_run_parallel() {
+        let program_state = TestProgramState {
+            left: AtomicU64::new(0),
+            right: AtomicU64::new(0),
+        };
+        let mut message_queue = MessageQueue::new();
+        message_queue.push(8);
+        let mut ticks = 0;
+        let update_func =
+            |program_state: &mut TestProgramState,
+             _message_queue: &mut MessageQueue<u64>,
+             systems: Vec<Box<dyn ParallelSystem<TestProgramState, u64>>>| {
+                ticks += 1;
+                if 8 < ticks {
+                    assert_eq!(*program_state.left.get_mut(), 4 * 8);
+                    assert_eq!(*program_state.right.get_mut(), 4 * 8);
+                    Vec::new()
+                } else if systems.is_empty() {
+                    vec![
+                        Box::new(SumSystem { part: LEFT })
+                            as Box<dyn ParallelSystem<TestProgramState, u64>>,
+                        Box::new(SumSystem { part: RIGHT })
+                            as Box<dyn ParallelSystem<TestProgramState, u64>>,
+                    ]
+                } else {
+                    systems
+                }
+            };
+
+        // Both systems halve every message, so the messages of a tick always add up to 8, until
+        // the ones are not pushed again after the fourth tick.
+        run_parallel(program_state, message_queue, update_func);
+        assert_eq!(ticks, 9);
+    }
+}
@@ -0,0 +1,76 @@
+// src/priority.rs
+
+// The `priority.rs` module adds message priorities on top of the ordinary `MessageQueue`.
+// Rather than introducing a second queue type, a `PriorityMessageQueue<M>` is simply a
+// `MessageQueue` of `Prioritized<M>` values, so it works with `run` and every existing system.
+
+// - Prioritized Messages: Each message carries a `u8` priority. Higher values are delivered
+//   earlier within a tick. Plain messages convert into priority zero.
+
+// - Ordered Scheduling: `push_with_priority` places a message behind every queued message of
+//   equal or higher priority and ahead of the rest, keeping the next tick sorted by priority
+//   while preserving push order within a priority level. Messages queued with the plain `push`
+//   always go to the back.
+
+use crate::message_queue::MessageQueue;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prioritized<Message> {
+    pub priority: u8,
+    pub message: Message,
+}
+
+impl<Message> Prioritized<Message> {
+    pub fn new(message: Message, priority: u8) -> Self {
+        Self { priority, message }
+    }
+}
+
+impl<Message> From<Message> for Prioritized<Message> {
+    fn from(message: Message) -> Self {
+        Self::new(message, 0)
+    }
+}
+
+pub type PriorityMessageQueue<Message> = MessageQueue<Prioritized<Message>>;
+
+impl<Message> MessageQueue<Prioritized<Message>> {
+    // Like `push`, drops the message if the next tick is at its limit.
+    pub fn push_with_priority(&mut self, message: Message, priority: u8) {
+        if !self.admit() {
+            return;
+        }
+        let index = self
+            .next_tick_queue
+            .iter()
+            .position(|queued| queued.priority < priority)
+            .unwrap_or(self.next_tick_queue.len());
+        self.next_tick_queue
+            .insert(index, Prioritized::new(message, priority));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_push_with_priority() {
+        let mut queue: PriorityMessageQueue<char> = MessageQueue::new();
+        queue.push_with_priority('a', 1);
+        queue.push_with_priority('b', 5);
+        queue.push_with_priority('c', 1);
+        queue.push_with_priority('d', 5);
+        queue.push_with_priority('e', 0);
+        queue.next_tick();
+
+        let order: String = queue.iter().map(|queued| queued.message).collect();
+        assert_eq!(order, "bdace");
+    }
+
+    #[test]
+    fn test_from_message() {
+        assert_eq!(Prioritized::from('x'), Prioritized::new('x', 0));
+    }
+}
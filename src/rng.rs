@@ -0,0 +1,95 @@
+// src/rng.rs
+
+// The `rng.rs` module provides `Prng`, a small deterministic pseudo random number generator
+// (xorshift64*) for programs that need randomness without a platform entropy source. It is
+// meant to live in the program state, so systems draw from it like from any other resource.
+
+// - Replay: The whole generator is a single `u64`. `state` captures it and `from_state`
+//   rebuilds a generator that continues the exact same sequence. Storing the state alongside
+//   the rest of a program checkpoint makes a restored program draw the same numbers again,
+//   which keeps replays after a restore fully deterministic.
+
+// Zero is a fixed point of xorshift, so it is never used as a state.
+const ZERO_STATE_REPLACEMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prng {
+    state: u64,
+}
+
+impl Prng {
+    pub fn new(seed: u64) -> Self {
+        Self::from_state(seed)
+    }
+
+    pub fn from_state(state: u64) -> Self {
+        Self {
+            state: if 0 == state {
+                ZERO_STATE_REPLACEMENT
+            } else {
+                state
+            },
+        }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // A number in `0..bound`. Panics if `bound` is zero.
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        assert!(0 < bound, "Bound must be positive.");
+        self.next_u64() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    struct TestProgramState {
+        rng: Prng,
+        rolls: Vec<u64>,
+    }
+
+    fn advance(program_state: &mut TestProgramState, ticks: usize) {
+        for _ in 0..ticks {
+            let roll = program_state.rng.next_below(6);
+            program_state.rolls.push(roll);
+        }
+    }
+
+    #[test]
+    fn test_restore_replays_sequence() {
+        let mut program_state = TestProgramState {
+            rng: Prng::new(42),
+            rolls: Vec::new(),
+        };
+        advance(&mut program_state, 3);
+
+        let checkpoint = (program_state.rng.state(), program_state.rolls.len());
+        advance(&mut program_state, 5);
+        let first_run = program_state.rolls[checkpoint.1..].to_vec();
+
+        program_state.rng = Prng::from_state(checkpoint.0);
+        program_state.rolls.truncate(checkpoint.1);
+        advance(&mut program_state, 5);
+
+        assert_eq!(program_state.rolls[checkpoint.1..], first_run);
+    }
+
+    #[test]
+    fn test_zero_seed() {
+        let mut rng = Prng::new(0);
+        assert_ne!(rng.state(), 0);
+        assert_ne!(rng.next_u64(), rng.next_u64());
+    }
+}
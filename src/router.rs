@@ -0,0 +1,195 @@
+// src/router.rs
+
+// The `router.rs` module provides `MultiQueueRouter`, an alternative to every system scanning
+// one shared queue of a single message enum. Larger programs can give each message type its
+// own `MessageQueue` and its own subscribers instead.
+
+// - Routes: The router keeps one route per message type, made of a queue and the systems
+//   subscribed to that type. Messages pushed through the router land in the queue of their
+//   type, and a system only ever sees the queue of the type it subscribed to.
+
+// - Lockstep: Each router tick first advances every queue, then updates the subscribers of
+//   each route in the order the routes were created. All message types therefore move through
+//   the same ticks together, exactly as a single queue would.
+
+// - Scheduling: The router is itself a `System`, so it can be dropped into an ordinary `run`
+//   loop. It ignores the outer queue and ticks its routes whenever it is updated.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+
+trait Route<ProgramState> {
+    fn message_type(&self) -> TypeId;
+    fn next_tick(&mut self);
+    fn tick(&mut self, program_state: &mut ProgramState);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedRoute<ProgramState, Message> {
+    queue: MessageQueue<Message>,
+    systems: Vec<Box<dyn System<ProgramState, Message>>>,
+}
+
+impl<ProgramState: 'static, Message: 'static> Route<ProgramState>
+    for TypedRoute<ProgramState, Message>
+{
+    fn message_type(&self) -> TypeId {
+        TypeId::of::<Message>()
+    }
+
+    fn next_tick(&mut self) {
+        self.queue.next_tick();
+    }
+
+    fn tick(&mut self, program_state: &mut ProgramState) {
+        for system in self.systems.iter_mut() {
+            system.update(program_state, &mut self.queue);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub struct MultiQueueRouter<ProgramState> {
+    routes: Vec<Box<dyn Route<ProgramState>>>,
+}
+
+impl<ProgramState: 'static> MultiQueueRouter<ProgramState> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    // Subscribes `system` to messages of type `Message`. Subscribers of the same type are
+    // updated in the order they subscribed.
+    pub fn subscribe<Message: 'static>(
+        &mut self,
+        system: Box<dyn System<ProgramState, Message>>,
+    ) -> &mut Self {
+        self.route_mut::<Message>().systems.push(system);
+        self
+    }
+
+    // Queues `message` for the next tick of its type's queue.
+    pub fn push<Message: 'static>(&mut self, message: Message) {
+        self.route_mut::<Message>().queue.push(message);
+    }
+
+    pub fn queue<Message: 'static>(&mut self) -> &mut MessageQueue<Message> {
+        &mut self.route_mut::<Message>().queue
+    }
+
+    // Advances every queue, then updates the subscribers of each route.
+    pub fn tick(&mut self, program_state: &mut ProgramState) {
+        for route in self.routes.iter_mut() {
+            route.next_tick();
+        }
+        for route in self.routes.iter_mut() {
+            route.tick(program_state);
+        }
+    }
+
+    // The route for `Message`, created empty on first use.
+    fn route_mut<Message: 'static>(&mut self) -> &mut TypedRoute<ProgramState, Message> {
+        let index = match self
+            .routes
+            .iter()
+            .position(|route| route.message_type() == TypeId::of::<Message>())
+        {
+            Some(index) => index,
+            None => {
+                self.routes
+                    .push(Box::new(TypedRoute::<ProgramState, Message> {
+                        queue: MessageQueue::new(),
+                        systems: Vec::new(),
+                    }));
+                self.routes.len() - 1
+            }
+        };
+        self.routes[index]
+            .as_any_mut()
+            .downcast_mut()
+            .expect("Route type mismatch.")
+    }
+}
+
+impl<ProgramState: 'static> Default for MultiQueueRouter<ProgramState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ProgramState: 'static, Message> System<ProgramState, Message>
+    for MultiQueueRouter<ProgramState>
+{
+    fn update(&mut self, program_state: &mut ProgramState, _messages: &mut MessageQueue<Message>) {
+        self.tick(program_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestProgramState {
+        altitude: i32,
+        warnings: u32,
+    }
+
+    struct AltitudeMessage(i32);
+
+    struct WarningMessage;
+
+    struct AltitudeSystem;
+
+    impl System<TestProgramState, AltitudeMessage> for AltitudeSystem {
+        fn update(
+            &mut self,
+            program_state: &mut TestProgramState,
+            messages: &mut MessageQueue<AltitudeMessage>,
+        ) {
+            for AltitudeMessage(altitude) in messages.iter() {
+                program_state.altitude = *altitude;
+            }
+        }
+    }
+
+    struct WarningSystem;
+
+    impl System<TestProgramState, WarningMessage> for WarningSystem {
+        fn update(
+            &mut self,
+            program_state: &mut TestProgramState,
+            messages: &mut MessageQueue<WarningMessage>,
+        ) {
+            program_state.warnings += messages.iter().count() as u32;
+        }
+    }
+
+    #[test]
+    fn test_messages_routed_by_type() {
+        let mut router = MultiQueueRouter::new();
+        router
+            .subscribe(Box::new(AltitudeSystem))
+            .subscribe(Box::new(WarningSystem));
+        let mut program_state = TestProgramState::default();
+
+        router.push(AltitudeMessage(1200));
+        router.push(WarningMessage);
+        router.push(WarningMessage);
+        router.tick(&mut program_state);
+        assert_eq!(program_state.altitude, 1200);
+        assert_eq!(program_state.warnings, 2);
+
+        // Both queues advance together, so nothing is seen twice.
+        router
+            .queue::<AltitudeMessage>()
+            .push(AltitudeMessage(1500));
+        router.tick(&mut program_state);
+        assert_eq!(program_state.altitude, 1500);
+        assert_eq!(program_state.warnings, 2);
+    }
+}
@@ -37,8 +37,24 @@
 // In summary, the `run` module is a testament to the Flight Brain framework's capabilities in handling intricate program flows and
 // system interactions, making it a valuable tool for developers looking to build advanced and dynamic applications.
 
-use crate::{message_queue::MessageQueue, system::System};
-use alloc::{boxed::Box, vec, vec::Vec};
+use crate::{
+    clock::Clock,
+    expiring::Expiring,
+    message_queue::MessageQueue,
+    system::{MessageMask, System, TickContext},
+};
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    ops::ControlFlow,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
 
 pub fn run<ProgramState, Message, UpdateFunc>(
     mut program_state: ProgramState,
@@ -51,68 +67,2680 @@ pub fn run<ProgramState, Message, UpdateFunc>(
         Vec<Box<dyn System<ProgramState, Message>>>,
     ) -> Vec<Box<dyn System<ProgramState, Message>>>,
 {
-    let mut systems = update(&mut program_state, &mut message_queue, vec![]);
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+}
+
+// Same as `run`, but once the loop ends the final program state is turned into a process
+// exit code by `exit_code`, e.g. nonzero after an error-terminated shutdown.
+pub fn run_with_exit_code<ProgramState, Message, UpdateFunc, ExitCodeFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    exit_code: ExitCodeFunc,
+) -> i32
+where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    ExitCodeFunc: FnOnce(&ProgramState) -> i32,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+    exit_code(&program_state)
+}
+
+// Same as `run`, but the program state is borrowed instead of moved, so the caller keeps
+// ownership and can inspect the final state afterwards.
+pub fn run_borrowed<ProgramState, Message, UpdateFunc>(
+    program_state: &mut ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    run_loop(
+        program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+}
+
+// The loop shared by `run` and all of its variants. The variants differ only in the hooks
+// they pass and in how they wrap the update closure.
+fn run_loop<ProgramState, Message, UpdateFunc, Hooks>(
+    program_state: &mut ProgramState,
+    message_queue: &mut MessageQueue<Message>,
+    update: &mut UpdateFunc,
+    hooks: &mut Hooks,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    Hooks: TickHooks<ProgramState, Message>,
+{
+    let mut systems = update(program_state, message_queue, vec![]);
+
+    while !systems.is_empty() {
+        if run_tick(&mut systems, program_state, message_queue, hooks).is_break() {
+            return;
+        }
+        systems = update(program_state, message_queue, systems);
+        hooks.systems_updated();
+    }
+}
+
+// One tick of the loop: advance the queue, reserve room for the systems' push hints and
+// update every system, calling the hooks in between.
+fn run_tick<ProgramState, Message, Hooks>(
+    systems: &mut [Box<dyn System<ProgramState, Message>>],
+    program_state: &mut ProgramState,
+    message_queue: &mut MessageQueue<Message>,
+    hooks: &mut Hooks,
+) -> ControlFlow<()>
+where
+    Hooks: TickHooks<ProgramState, Message>,
+{
+    hooks.before_tick(message_queue);
+    hooks.advance(message_queue);
+    message_queue.reserve(systems.iter().map(|system| system.push_hint()).sum());
+    hooks.tick_started(program_state, message_queue);
+    hooks.update_systems(systems, program_state, message_queue)?;
+    hooks.tick_finished(program_state, message_queue)
+}
+
+// The points at which a `run` variant adds behaviour to a tick. Every hook defaults to what
+// plain `run` does, so a variant only implements the ones it changes. A hook that returns
+// `Break` ends the loop immediately, skipping the rest of the tick and the update closure.
+trait TickHooks<ProgramState, Message> {
+    // Called while the messages pushed during the previous tick still wait for the next one.
+    fn before_tick(&mut self, _message_queue: &mut MessageQueue<Message>) {}
+
+    fn advance(&mut self, message_queue: &mut MessageQueue<Message>) {
+        message_queue.next_tick();
+    }
+
+    // Called once the new tick is current, before any system is updated.
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        _message_queue: &mut MessageQueue<Message>,
+    ) {
+    }
+
+    fn update_systems(
+        &mut self,
+        systems: &mut [Box<dyn System<ProgramState, Message>>],
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        for (system_index, system) in systems.iter_mut().enumerate() {
+            self.update_system(system_index, system.as_mut(), program_state, message_queue)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn update_system(
+        &mut self,
+        _system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        system.update(program_state, message_queue);
+        ControlFlow::Continue(())
+    }
+
+    // Called after the last system, before the update closure.
+    fn tick_finished(
+        &mut self,
+        _program_state: &mut ProgramState,
+        _message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    // Called once the update closure has returned the systems for the next tick.
+    fn systems_updated(&mut self) {}
+}
+
+// The hooks of plain `run`.
+struct PlainTick;
+
+impl<ProgramState, Message> TickHooks<ProgramState, Message> for PlainTick {}
+
+// Information about the loop handed to the update closure by `run_with_context`.
+// `system_count` is the number of systems that were updated during the tick that
+// just finished, or zero before the first tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunContext {
+    pub tick: u64,
+    pub system_count: usize,
+}
+
+// Same as `run`, but the update closure also receives a `RunContext` so it can
+// adapt its scheduling to the current load.
+pub fn run_with_context<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+        &RunContext,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    let mut context = RunContext::default();
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut |program_state, message_queue, systems| {
+            // Only the initial call comes without systems.
+            if !systems.is_empty() {
+                context.tick += 1;
+            }
+            context.system_count = systems.len();
+            update(program_state, message_queue, systems, &context)
+        },
+        &mut PlainTick,
+    );
+}
+
+// Identifies where `run_with_barrier` detected a violated invariant. `system_index` is the
+// position in the systems vector of the system whose update broke the invariant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierFault {
+    pub tick: u64,
+    pub system_index: usize,
+}
+
+// Same as `run`, but `barrier` is checked after every individual system update. If it
+// returns false the loop halts immediately and reports the offending system.
+pub fn run_with_barrier<ProgramState, Message, UpdateFunc, BarrierFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    barrier: BarrierFunc,
+) -> Result<(), BarrierFault>
+where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    BarrierFunc: FnMut(&ProgramState, &MessageQueue<Message>) -> bool,
+{
+    let mut hooks = BarrierTick {
+        barrier,
+        tick: 0,
+        fault: None,
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+    match hooks.fault {
+        Some(fault) => Err(fault),
+        None => Ok(()),
+    }
+}
+
+struct BarrierTick<BarrierFunc> {
+    barrier: BarrierFunc,
+    tick: u64,
+    fault: Option<BarrierFault>,
+}
+
+impl<ProgramState, Message, BarrierFunc> TickHooks<ProgramState, Message>
+    for BarrierTick<BarrierFunc>
+where
+    BarrierFunc: FnMut(&ProgramState, &MessageQueue<Message>) -> bool,
+{
+    fn before_tick(&mut self, _message_queue: &mut MessageQueue<Message>) {
+        self.tick += 1;
+    }
+
+    fn update_system(
+        &mut self,
+        system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        system.update(program_state, message_queue);
+        if (self.barrier)(program_state, message_queue) {
+            return ControlFlow::Continue(());
+        }
+        self.fault = Some(BarrierFault {
+            tick: self.tick,
+            system_index,
+        });
+        ControlFlow::Break(())
+    }
+}
+
+// Same as `run`, but each system only sees the current-tick messages it subscribes to.
+// `kind_of` maps a message to the bit index used in `System::subscriptions`. A mask only has
+// 64 bits, so a message whose kind is 64 or more matches no mask and is only delivered to
+// systems subscribed to `MessageMask::ALL`.
+pub fn run_subscribed<ProgramState, Message, UpdateFunc, KindFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    kind_of: KindFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    KindFunc: Fn(&Message) -> u32,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut SubscribedTick(kind_of),
+    );
+}
+
+struct SubscribedTick<KindFunc>(KindFunc);
+
+impl<ProgramState, Message, KindFunc> TickHooks<ProgramState, Message> for SubscribedTick<KindFunc>
+where
+    KindFunc: Fn(&Message) -> u32,
+{
+    fn update_system(
+        &mut self,
+        _system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        update_subscribed(system, program_state, message_queue, &self.0);
+        ControlFlow::Continue(())
+    }
+}
+
+// Hides unsubscribed messages for the duration of one update. Hidden messages are put back
+// at their original positions, so later systems see the tick in its usual order.
+fn update_subscribed<ProgramState, Message, KindFunc>(
+    system: &mut dyn System<ProgramState, Message>,
+    program_state: &mut ProgramState,
+    message_queue: &mut MessageQueue<Message>,
+    kind_of: &KindFunc,
+) where
+    KindFunc: Fn(&Message) -> u32,
+{
+    let mask = system.subscriptions();
+    if MessageMask::ALL == mask {
+        system.update(program_state, message_queue);
+        return;
+    }
+
+    let mut visible = VecDeque::new();
+    let mut hidden = Vec::new();
+    for (index, message) in message_queue.current_tick_queue.drain(..).enumerate() {
+        if mask.contains(kind_of(&message)) {
+            visible.push_back(message);
+        } else {
+            hidden.push((index, message));
+        }
+    }
+    message_queue.current_tick_queue = visible;
+
+    system.update(program_state, message_queue);
+
+    // Merge in one pass: every hidden message goes back to its original index, and the
+    // messages left in the tick fill the gaps in between in their current order.
+    let mut remaining = core::mem::take(&mut message_queue.current_tick_queue).into_iter();
+    let mut merged = VecDeque::with_capacity(remaining.len() + hidden.len());
+    for (index, message) in hidden {
+        merged.extend(remaining.by_ref().take(index.saturating_sub(merged.len())));
+        merged.push_back(message);
+    }
+    merged.extend(remaining);
+    message_queue.current_tick_queue = merged;
+}
+
+// Same as `run`, but the program state is cloned around every system update. Whenever a
+// system leaves the state different from how it found it, `observer` is called with the
+// tick, the index of the system, and the state before and after the update.
+pub fn run_with_state_observer<ProgramState, Message, UpdateFunc, ObserverFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    observer: ObserverFunc,
+) where
+    ProgramState: Clone + PartialEq,
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    ObserverFunc: FnMut(u64, usize, &ProgramState, &ProgramState),
+{
+    let mut hooks = ObservedTick { observer, tick: 0 };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+}
+
+struct ObservedTick<ObserverFunc> {
+    observer: ObserverFunc,
+    tick: u64,
+}
+
+impl<ProgramState, Message, ObserverFunc> TickHooks<ProgramState, Message>
+    for ObservedTick<ObserverFunc>
+where
+    ProgramState: Clone + PartialEq,
+    ObserverFunc: FnMut(u64, usize, &ProgramState, &ProgramState),
+{
+    fn before_tick(&mut self, _message_queue: &mut MessageQueue<Message>) {
+        self.tick += 1;
+    }
+
+    fn update_system(
+        &mut self,
+        system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        let before = program_state.clone();
+        system.update(program_state, message_queue);
+        if before != *program_state {
+            (self.observer)(self.tick, system_index, &before, program_state);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// What `run_with_queue_limit` does with next-tick messages above the high-water mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // Only raise the alarm; every message is delivered.
+    Keep,
+    // Discard the most recently pushed messages.
+    DropNewest,
+    // Discard the earliest pushed messages.
+    DropOldest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimit {
+    pub high_water_mark: usize,
+    pub policy: OverflowPolicy,
+}
+
+// Same as `run`, but before each tick the number of messages waiting for the next tick is
+// compared against `limit`. When it exceeds the high-water mark, `on_high_water` is called
+// with the tick about to run and the observed size, and then the overflow policy is applied.
+pub fn run_with_queue_limit<ProgramState, Message, UpdateFunc, AlarmFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    limit: QueueLimit,
+    on_high_water: AlarmFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    AlarmFunc: FnMut(u64, usize),
+{
+    let mut hooks = QueueLimitTick {
+        limit,
+        on_high_water,
+        tick: 0,
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+}
+
+struct QueueLimitTick<AlarmFunc> {
+    limit: QueueLimit,
+    on_high_water: AlarmFunc,
+    tick: u64,
+}
+
+impl<ProgramState, Message, AlarmFunc> TickHooks<ProgramState, Message>
+    for QueueLimitTick<AlarmFunc>
+where
+    AlarmFunc: FnMut(u64, usize),
+{
+    fn before_tick(&mut self, message_queue: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        let size = message_queue.next_tick_queue.len();
+        if self.limit.high_water_mark < size {
+            (self.on_high_water)(self.tick, size);
+            let excess = size - self.limit.high_water_mark;
+            match self.limit.policy {
+                OverflowPolicy::Keep => (),
+                OverflowPolicy::DropNewest => message_queue
+                    .next_tick_queue
+                    .truncate(self.limit.high_water_mark),
+                OverflowPolicy::DropOldest => {
+                    message_queue.next_tick_queue.drain(..excess);
+                }
+            }
+        }
+    }
+}
+
+// A boxed update closure, as taken by `run_composed`.
+pub type BoxedUpdateFunc<ProgramState, Message> = Box<
+    dyn FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+>;
+
+// Same as `run`, but system management is split across several update closures. Each closure
+// owns its own group of systems, and the groups are updated in the order of `updates`. The
+// loop ends once every closure has returned an empty group.
+pub fn run_composed<ProgramState, Message>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut updates: Vec<BoxedUpdateFunc<ProgramState, Message>>,
+) {
+    // The systems of all groups run as one flat list; `lengths` remembers where each group
+    // starts so the list can be split up again for the update closures.
+    let mut lengths = vec![0; updates.len()];
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut |program_state, message_queue, systems| {
+            let mut systems = systems.into_iter();
+            let mut joined = Vec::new();
+            for (update, length) in updates.iter_mut().zip(lengths.iter_mut()) {
+                let group = systems.by_ref().take(*length).collect();
+                let group = update(program_state, message_queue, group);
+                *length = group.len();
+                joined.extend(group);
+            }
+            joined
+        },
+        &mut PlainTick,
+    );
+}
+
+// A runtime control request recognized by `run_suspendable`, naming the system it targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemControl<'a> {
+    Suspend(&'a str),
+    Resume(&'a str),
+}
+
+// Same as `run`, but systems can be suspended and resumed by name at runtime. After each
+// `next_tick`, `control` is asked about every current-tick message; suspend and resume
+// requests take effect for that same tick. Suspended systems stay in the systems vector but
+// are not updated. The control messages remain in the queue for the systems to see.
+pub fn run_suspendable<ProgramState, Message, UpdateFunc, ControlFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    control: ControlFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    ControlFunc: Fn(&Message) -> Option<SystemControl<'_>>,
+{
+    let mut hooks = SuspendableTick {
+        control,
+        suspended: Vec::new(),
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+}
+
+struct SuspendableTick<ControlFunc> {
+    control: ControlFunc,
+    suspended: Vec<String>,
+}
+
+impl<ProgramState, Message, ControlFunc> TickHooks<ProgramState, Message>
+    for SuspendableTick<ControlFunc>
+where
+    ControlFunc: Fn(&Message) -> Option<SystemControl<'_>>,
+{
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        for message in message_queue.iter() {
+            match (self.control)(message) {
+                Some(SystemControl::Suspend(name))
+                    if !self
+                        .suspended
+                        .iter()
+                        .any(|suspended_name| suspended_name == name) =>
+                {
+                    self.suspended.push(name.to_string());
+                }
+                Some(SystemControl::Resume(name)) => {
+                    self.suspended
+                        .retain(|suspended_name| suspended_name != name);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn update_system(
+        &mut self,
+        _system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        if !self.suspended.iter().any(|name| name == system.name()) {
+            system.update(program_state, message_queue);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// What a `SwappableUpdateFunc` returns: the systems for the next tick, optionally together
+// with the update closure that takes over from the next call onwards.
+pub enum UpdateControl<ProgramState, Message> {
+    Continue(Vec<Box<dyn System<ProgramState, Message>>>),
+    Swap(
+        Vec<Box<dyn System<ProgramState, Message>>>,
+        SwappableUpdateFunc<ProgramState, Message>,
+    ),
+}
+
+pub type SwappableUpdateFunc<ProgramState, Message> = Box<
+    dyn FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> UpdateControl<ProgramState, Message>,
+>;
+
+// Same as `run`, but the update closure may replace itself at runtime by returning
+// `UpdateControl::Swap`, e.g. to move from startup scheduling to cruise scheduling.
+pub fn run_swappable<ProgramState, Message>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: SwappableUpdateFunc<ProgramState, Message>,
+) {
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut |program_state, message_queue, systems| {
+            update_swappable(&mut update, program_state, message_queue, systems)
+        },
+        &mut PlainTick,
+    );
+}
+
+fn update_swappable<ProgramState, Message>(
+    update: &mut SwappableUpdateFunc<ProgramState, Message>,
+    program_state: &mut ProgramState,
+    message_queue: &mut MessageQueue<Message>,
+    systems: Vec<Box<dyn System<ProgramState, Message>>>,
+) -> Vec<Box<dyn System<ProgramState, Message>>> {
+    match update(program_state, message_queue, systems) {
+        UpdateControl::Continue(systems) => systems,
+        UpdateControl::Swap(systems, replacement) => {
+            *update = replacement;
+            systems
+        }
+    }
+}
+
+// Same as `run`, but for expiring messages. Ticks advance with `next_tick_expiring`, so
+// messages that waited in the queue until their TTL ran out are dropped instead of delivered.
+pub fn run_expiring<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Expiring<Message>>,
+    mut update: UpdateFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Expiring<Message>>,
+        Vec<Box<dyn System<ProgramState, Expiring<Message>>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Expiring<Message>>>>,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut ExpiringTick,
+    );
+}
+
+struct ExpiringTick;
+
+impl<ProgramState, Message> TickHooks<ProgramState, Expiring<Message>> for ExpiringTick {
+    fn advance(&mut self, message_queue: &mut MessageQueue<Expiring<Message>>) {
+        message_queue.next_tick_expiring();
+    }
+}
+
+// Same as `run`, but every message delivered in a tick is offered to `extract`, and the
+// strings it returns are collected in delivery order. Returns the final program state
+// together with the collected lines, so tests can assert on log output directly.
+pub fn run_collecting<ProgramState, Message, UpdateFunc, ExtractFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    extract: ExtractFunc,
+) -> (ProgramState, Vec<String>)
+where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    ExtractFunc: Fn(&Message) -> Option<String>,
+{
+    let mut hooks = CollectingTick {
+        extract,
+        collected: Vec::new(),
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+    (program_state, hooks.collected)
+}
+
+struct CollectingTick<ExtractFunc> {
+    extract: ExtractFunc,
+    collected: Vec<String>,
+}
+
+impl<ProgramState, Message, ExtractFunc> TickHooks<ProgramState, Message>
+    for CollectingTick<ExtractFunc>
+where
+    ExtractFunc: Fn(&Message) -> Option<String>,
+{
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        self.collected
+            .extend(message_queue.iter().filter_map(&self.extract));
+    }
+}
+
+// Same as `run`, but messages still queued when the loop ends are not lost. If the last tick
+// left anything in the next-tick queue, the queue advances once more and `drain` is handed
+// the program state and the queue so it can process what is left, e.g. print a final result.
+pub fn run_with_drain<ProgramState, Message, UpdateFunc, DrainFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    drain: DrainFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    DrainFunc: FnOnce(&mut ProgramState, &mut MessageQueue<Message>),
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+    if message_queue.peek_next().is_some() {
+        message_queue.next_tick();
+        drain(&mut program_state, &mut message_queue);
+    }
+}
+
+// Same as `run`, but a tick that ends with nothing left in either buffer of the queue calls
+// `on_idle`. The callback may push messages to get the program going again; returning false
+// ends the loop. This replaces systems that push a message whenever the queue runs dry.
+pub fn run_with_idle<ProgramState, Message, UpdateFunc, IdleFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    on_idle: IdleFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    IdleFunc: FnMut(&mut ProgramState, &mut MessageQueue<Message>) -> bool,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut IdleTick(on_idle),
+    );
+}
+
+struct IdleTick<IdleFunc>(IdleFunc);
+
+impl<ProgramState, Message, IdleFunc> TickHooks<ProgramState, Message> for IdleTick<IdleFunc>
+where
+    IdleFunc: FnMut(&mut ProgramState, &mut MessageQueue<Message>) -> bool,
+{
+    fn tick_finished(
+        &mut self,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        if message_queue.iter_all().next().is_none() && !(self.0)(program_state, message_queue) {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// The outcome of one `run_cooperative` call: the final program state once the loop is over,
+// or the suspended run to pass to the next call.
+pub enum RunPoll<ProgramState, Pending> {
+    Ready(ProgramState),
+    Pending(Pending),
+}
+
+// A `run` loop suspended between ticks, for `run_cooperative`.
+pub struct CooperativeRun<ProgramState, Message, UpdateFunc> {
+    program_state: ProgramState,
+    message_queue: MessageQueue<Message>,
+    update: UpdateFunc,
+    systems: Option<Vec<Box<dyn System<ProgramState, Message>>>>,
+}
+
+impl<ProgramState, Message, UpdateFunc> CooperativeRun<ProgramState, Message, UpdateFunc> {
+    pub fn new(
+        program_state: ProgramState,
+        message_queue: MessageQueue<Message>,
+        update: UpdateFunc,
+    ) -> Self {
+        Self {
+            program_state,
+            message_queue,
+            update,
+            systems: None,
+        }
+    }
+
+    pub fn program_state(&self) -> &ProgramState {
+        &self.program_state
+    }
+}
+
+// Runs the loop for one tick and hands control back, so a cooperative scheduler such as an
+// RTOS task can pump the program without giving up its own flow. The first call also performs
+// the initial update that creates the systems. Ticks behave exactly as in `run`.
+pub fn run_cooperative<ProgramState, Message, UpdateFunc>(
+    mut run: CooperativeRun<ProgramState, Message, UpdateFunc>,
+) -> RunPoll<ProgramState, CooperativeRun<ProgramState, Message, UpdateFunc>>
+where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    let mut systems = match run.systems.take() {
+        Some(systems) => systems,
+        None => (run.update)(&mut run.program_state, &mut run.message_queue, vec![]),
+    };
+
+    if !systems.is_empty() {
+        // Plain ticks never break.
+        let _ = run_tick(
+            &mut systems,
+            &mut run.program_state,
+            &mut run.message_queue,
+            &mut PlainTick,
+        );
+        systems = (run.update)(&mut run.program_state, &mut run.message_queue, systems);
+    }
+
+    if systems.is_empty() {
+        RunPoll::Ready(run.program_state)
+    } else {
+        run.systems = Some(systems);
+        RunPoll::Pending(run)
+    }
+}
+
+// A source of hardware events, such as flags set by interrupt service routines.
+pub trait InterruptSource<Message> {
+    // Returns the next pending event as a message, or `None` when nothing is pending.
+    fn poll(&mut self) -> Option<Message>;
+}
+
+// Same as `run`, but at the start of every tick the interrupt source is polled until it has
+// nothing more to report. The events are added to the current tick after the messages pushed
+// during the previous tick, so systems react to them in the same tick they were picked up.
+pub fn run_with_interrupts<ProgramState, Message, UpdateFunc, Source>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    source: Source,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    Source: InterruptSource<Message>,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut InterruptTick(source),
+    );
+}
+
+struct InterruptTick<Source>(Source);
+
+impl<ProgramState, Message, Source> TickHooks<ProgramState, Message> for InterruptTick<Source>
+where
+    Source: InterruptSource<Message>,
+{
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        while let Some(message) = self.0.poll() {
+            message_queue.current_tick_queue.push_back(message);
+        }
+    }
+}
+
+// Tick durations sorted into buckets. Bucket `i` counts the durations up to `bounds_ms[i]`
+// that did not fit an earlier bucket, and one extra bucket at the end counts everything
+// longer than the last bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    bounds_ms: Vec<u64>,
+    counts: Vec<u64>,
+    max_ms: u64,
+}
+
+impl LatencyHistogram {
+    // `bounds_ms` must be sorted in ascending order.
+    pub fn new(bounds_ms: &[u64]) -> Self {
+        Self {
+            bounds_ms: bounds_ms.to_vec(),
+            counts: vec![0; bounds_ms.len() + 1],
+            max_ms: 0,
+        }
+    }
+
+    pub fn record(&mut self, duration_ms: u64) {
+        let bucket = self
+            .bounds_ms
+            .partition_point(|bound_ms| *bound_ms < duration_ms);
+        self.counts[bucket] += 1;
+        self.max_ms = self.max_ms.max(duration_ms);
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    // The worst-case tick duration recorded so far.
+    pub fn max_ms(&self) -> u64 {
+        self.max_ms
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+// Same as `run`, but the duration of every tick, including the update closure, is measured
+// with `clock` and recorded in `histogram`, which the caller keeps for analysis afterwards.
+pub fn run_with_histogram<ProgramState, Message, UpdateFunc, C>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    clock: &mut C,
+    histogram: &mut LatencyHistogram,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    C: Clock,
+{
+    let mut hooks = HistogramTick {
+        clock,
+        histogram,
+        tick_start_ms: None,
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+}
+
+struct HistogramTick<'a, C> {
+    clock: &'a mut C,
+    histogram: &'a mut LatencyHistogram,
+    // When the current tick started; only the first tick reads it in `before_tick`, every
+    // later one starts when the previous one ended.
+    tick_start_ms: Option<u64>,
+}
+
+impl<ProgramState, Message, C: Clock> TickHooks<ProgramState, Message> for HistogramTick<'_, C> {
+    fn before_tick(&mut self, _message_queue: &mut MessageQueue<Message>) {
+        if self.tick_start_ms.is_none() {
+            self.tick_start_ms = Some(self.clock.now_ms());
+        }
+    }
+
+    fn systems_updated(&mut self) {
+        let tick_end_ms = self.clock.now_ms();
+        if let Some(tick_start_ms) = self.tick_start_ms {
+            self.histogram.record(tick_end_ms - tick_start_ms);
+        }
+        self.tick_start_ms = Some(tick_end_ms);
+    }
+}
+
+// Same as `run`, but every tick has a time budget of `budget_ms`, measured with `clock` from
+// the start of the tick. Before each system after the first, the loop checks the elapsed time;
+// once the budget is used up, the remaining systems are deferred and updated first in the
+// next tick, followed by the others in their usual order. This bounds tick latency under load
+// while every system keeps making progress. A deferred system misses the messages of the tick
+// it was deferred from.
+pub fn run_paced<ProgramState, Message, UpdateFunc, C>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    clock: &mut C,
+    budget_ms: u64,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    C: Clock,
+{
+    let mut hooks = PacedTick {
+        clock,
+        budget_ms,
+        tick_start_ms: 0,
+        next_system: 0,
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+}
+
+struct PacedTick<'a, C> {
+    clock: &'a mut C,
+    budget_ms: u64,
+    tick_start_ms: u64,
+    // The first system deferred by the previous tick.
+    next_system: usize,
+}
+
+impl<ProgramState, Message, C: Clock> TickHooks<ProgramState, Message> for PacedTick<'_, C> {
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        _message_queue: &mut MessageQueue<Message>,
+    ) {
+        self.tick_start_ms = self.clock.now_ms();
+    }
+
+    fn update_systems(
+        &mut self,
+        systems: &mut [Box<dyn System<ProgramState, Message>>],
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        let count = systems.len();
+        // The update closure may have removed the deferred systems in the meantime.
+        let start = if self.next_system < count {
+            self.next_system
+        } else {
+            0
+        };
+        self.next_system = 0;
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            if 0 < offset && self.budget_ms <= self.clock.now_ms() - self.tick_start_ms {
+                self.next_system = index;
+                break;
+            }
+            systems[index].update(program_state, message_queue);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// Same as `run`, but every tick `middleware` sees the full current tick after `next_tick` and
+// before any system, making it the single place for cross-cutting concerns such as logging,
+// metrics or filtering.
+pub fn run_with_middleware<ProgramState, Message, UpdateFunc, MiddlewareFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    middleware: MiddlewareFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    MiddlewareFunc: FnMut(&mut MessageQueue<Message>),
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut MiddlewareTick(middleware),
+    );
+}
+
+struct MiddlewareTick<MiddlewareFunc>(MiddlewareFunc);
+
+impl<ProgramState, Message, MiddlewareFunc> TickHooks<ProgramState, Message>
+    for MiddlewareTick<MiddlewareFunc>
+where
+    MiddlewareFunc: FnMut(&mut MessageQueue<Message>),
+{
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        (self.0)(message_queue);
+    }
+}
+
+// Why a loop ended, as reported by `run_with_on_exit` and `run_with_message_limit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    // The update closure returned no systems and no messages were left.
+    Finished,
+    // The update closure returned no systems while messages were still queued for the next
+    // tick; they are discarded.
+    PendingMessages(usize),
+    // `run_with_message_limit` processed as many messages as it was allowed to.
+    MessageLimit,
+}
+
+// Same as `run`, but `on_exit` is called once after the loop ends with the final program
+// state and the reason the loop ended, e.g. to flush logs or set a status.
+pub fn run_with_on_exit<ProgramState, Message, UpdateFunc, ExitFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    on_exit: ExitFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+    ExitFunc: FnOnce(&ProgramState, ExitReason),
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+    on_exit(&program_state, exit_reason(&message_queue));
+}
+
+// The reason for a loop that ended because it ran out of systems.
+fn exit_reason<Message>(message_queue: &MessageQueue<Message>) -> ExitReason {
+    match message_queue.next_len() {
+        0 => ExitReason::Finished,
+        pending => ExitReason::PendingMessages(pending),
+    }
+}
+
+// Same as `run`, but the total work is bounded: the loop counts the current-tick messages of
+// every tick, and once the count reaches `max_messages` it ends after that tick, even though
+// systems remain. Returns why the loop ended.
+pub fn run_with_message_limit<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    max_messages: u64,
+) -> ExitReason
+where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    let mut hooks = MessageLimitTick {
+        max_messages,
+        processed: 0,
+        reached: false,
+    };
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut hooks,
+    );
+    if hooks.reached {
+        ExitReason::MessageLimit
+    } else {
+        exit_reason(&message_queue)
+    }
+}
+
+struct MessageLimitTick {
+    max_messages: u64,
+    processed: u64,
+    reached: bool,
+}
+
+impl<ProgramState, Message> TickHooks<ProgramState, Message> for MessageLimitTick {
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        self.processed += message_queue.len() as u64;
+    }
+
+    fn tick_finished(
+        &mut self,
+        _program_state: &mut ProgramState,
+        _message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        if self.max_messages <= self.processed {
+            self.reached = true;
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// Same as `run`, but systems are updated through `System::update_ctx` with a `TickContext`
+// carrying the current tick number, so programs need not count ticks in their state.
+pub fn run_with_tick_context<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut TickContextTick(TickContext { tick: 0 }),
+    );
+}
+
+struct TickContextTick(TickContext);
+
+impl<ProgramState, Message> TickHooks<ProgramState, Message> for TickContextTick {
+    fn tick_started(
+        &mut self,
+        _program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) {
+        self.0 = TickContext {
+            tick: message_queue.tick(),
+        };
+    }
+
+    fn update_system(
+        &mut self,
+        _system_index: usize,
+        system: &mut dyn System<ProgramState, Message>,
+        program_state: &mut ProgramState,
+        message_queue: &mut MessageQueue<Message>,
+    ) -> ControlFlow<()> {
+        system.update_ctx(&self.0, program_state, message_queue);
+        ControlFlow::Continue(())
+    }
+}
+
+// A hook for `run_with_abort_hook`. Hooks are declared as statics, so the registered hook
+// stays valid for as long as a panic handler might call it:
+// `static WATCHDOG: AbortHook = AbortHook::new(trip_watchdog);`
+pub struct AbortHook(fn());
+
+impl AbortHook {
+    pub const fn new(hook: fn()) -> Self {
+        Self(hook)
+    }
+}
+
+// The hook of the innermost running `run_with_abort_hook`, or null while no such loop is
+// running. Only ever points at a `&'static AbortHook`.
+static ABORT_HOOK: AtomicPtr<AbortHook> = AtomicPtr::new(ptr::null_mut());
+
+// Same as `run`, but `abort_hook` is registered for the duration of the loop. `no_std` builds
+// cannot unwind out of a failing system, so the program's `#[panic_handler]` should call
+// `call_abort_hook` before it loops forever, giving embedded programs a chance to trip a
+// hardware watchdog or raise a fault pin. There is a single global hook: a nested or
+// overlapping `run_with_abort_hook` replaces it until that loop ends, then the previous hook is
+// restored. Loops running on other threads at the same time cannot each keep their own hook.
+pub fn run_with_abort_hook<ProgramState, Message, UpdateFunc>(
+    mut program_state: ProgramState,
+    mut message_queue: MessageQueue<Message>,
+    mut update: UpdateFunc,
+    abort_hook: &'static AbortHook,
+) where
+    UpdateFunc: FnMut(
+        &mut ProgramState,
+        &mut MessageQueue<Message>,
+        Vec<Box<dyn System<ProgramState, Message>>>,
+    ) -> Vec<Box<dyn System<ProgramState, Message>>>,
+{
+    let hook = abort_hook as *const AbortHook as *mut AbortHook;
+    let _restore = RestoreAbortHook(ABORT_HOOK.swap(hook, Ordering::SeqCst));
+    run_loop(
+        &mut program_state,
+        &mut message_queue,
+        &mut update,
+        &mut PlainTick,
+    );
+}
+
+// Puts the previous hook back when a `run_with_abort_hook` loop ends, including by unwinding
+// in hosted builds.
+struct RestoreAbortHook(*mut AbortHook);
+
+impl Drop for RestoreAbortHook {
+    fn drop(&mut self) {
+        ABORT_HOOK.store(self.0, Ordering::SeqCst);
+    }
+}
+
+// Runs the hook registered by `run_with_abort_hook`, if any. Meant to be called from the
+// program's `#[panic_handler]`.
+pub fn call_abort_hook() {
+    let hook = ABORT_HOOK.load(Ordering::SeqCst);
+    // `ABORT_HOOK` is either null or points at a `&'static AbortHook`, which is never written
+    // through.
+    if let Some(hook) = unsafe { hook.as_ref() } {
+        (hook.0)();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use alloc::{format, rc::Rc};
+    use core::cell::RefCell;
+
+    struct TestProgramState {
+        done: bool,
+        sum: i32,
+    }
+
+    struct TestSystem;
+
+    impl System<TestProgramState, i32> for TestSystem {
+        fn update(
+            &mut self,
+            program_state: &mut TestProgramState,
+            message_queue: &mut MessageQueue<i32>,
+        ) {
+            for message_value in message_queue.iter() {
+                program_state.sum += message_value;
+            }
+            message_queue.push(program_state.sum);
+            if 10 < program_state.sum {
+                program_state.done = true;
+            }
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let message_queue = MessageQueue::new();
+        let update_func =
+            |program_state: &mut TestProgramState,
+             message_queue: &mut MessageQueue<i32>,
+             systems: Vec<Box<dyn System<TestProgramState, i32>>>| {
+                if program_state.done {
+                    // return empty vec to exit
+                    Vec::new()
+                } else if systems.is_empty() {
+                    // push startup messages
+                    message_queue.push(1);
+                    // initialize systems vec
+                    vec![Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>]
+                } else {
+                    systems
+                }
+            };
+
+        run(program_state, message_queue, update_func);
+    }
+
+    // The update closure of `test_run`, for the tests of variants that run it unchanged.
+    fn test_update_func(
+        program_state: &mut TestProgramState,
+        message_queue: &mut MessageQueue<i32>,
+        systems: Vec<Box<dyn System<TestProgramState, i32>>>,
+    ) -> Vec<Box<dyn System<TestProgramState, i32>>> {
+        if program_state.done {
+            Vec::new()
+        } else if systems.is_empty() {
+            message_queue.push(1);
+            vec![Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>]
+        } else {
+            systems
+        }
+    }
+
+    #[test]
+    fn test_run_with_context_reports_system_count() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let message_queue = MessageQueue::new();
+        let mut reported = Vec::new();
+        let mut expected = Vec::new();
+        let mut last_len = 0;
+        let update_func = |program_state: &mut TestProgramState,
+                           message_queue: &mut MessageQueue<i32>,
+                           mut systems: Vec<Box<dyn System<TestProgramState, i32>>>,
+                           context: &RunContext| {
+            reported.push((context.tick, context.system_count));
+            expected.push(last_len);
+            if program_state.done {
+                systems = Vec::new();
+            } else if systems.is_empty() {
+                message_queue.push(1);
+                systems = vec![Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>];
+            } else if systems.len() < 3 {
+                // grow the system set each tick
+                systems.push(Box::new(TestSystem));
+            }
+            last_len = systems.len();
+            systems
+        };
+
+        run_with_context(program_state, message_queue, update_func);
+
+        assert_eq!(reported[0], (0, 0));
+        for (index, (tick, system_count)) in reported.iter().enumerate() {
+            assert_eq!(*tick, index as u64);
+            assert_eq!(*system_count, expected[index]);
+        }
+        assert_eq!(reported[1].1, 1);
+        assert_eq!(reported[2].1, 2);
+    }
+
+    struct CorruptingSystem;
+
+    impl System<TestProgramState, i32> for CorruptingSystem {
+        fn update(
+            &mut self,
+            program_state: &mut TestProgramState,
+            _message_queue: &mut MessageQueue<i32>,
+        ) {
+            program_state.sum = -1;
+        }
+    }
+
+    #[test]
+    fn test_run_with_barrier_halts_on_fault() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let message_queue = MessageQueue::new();
+        let update_func =
+            |_program_state: &mut TestProgramState,
+             message_queue: &mut MessageQueue<i32>,
+             systems: Vec<Box<dyn System<TestProgramState, i32>>>| {
+                if systems.is_empty() {
+                    message_queue.push(1);
+                    vec![
+                        Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>,
+                        Box::new(CorruptingSystem) as Box<dyn System<TestProgramState, i32>>,
+                        Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>,
+                    ]
+                } else {
+                    systems
+                }
+            };
+        let barrier = |program_state: &TestProgramState, _message_queue: &MessageQueue<i32>| {
+            0 <= program_state.sum
+        };
+
+        let result = run_with_barrier(program_state, message_queue, update_func, barrier);
+        assert_eq!(
+            result,
+            Err(BarrierFault {
+                tick: 1,
+                system_index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_with_barrier_ok() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let message_queue = MessageQueue::new();
+        let barrier = |program_state: &TestProgramState, _message_queue: &MessageQueue<i32>| {
+            0 <= program_state.sum
+        };
+
+        assert_eq!(
+            run_with_barrier(program_state, message_queue, test_update_func, barrier),
+            Ok(())
+        );
+    }
+
+    struct SubscribedSystem {
+        mask: MessageMask,
+        received: Rc<RefCell<Vec<i32>>>,
+    }
+
+    impl System<TestProgramState, i32> for SubscribedSystem {
+        fn update(
+            &mut self,
+            program_state: &mut TestProgramState,
+            message_queue: &mut MessageQueue<i32>,
+        ) {
+            self.received
+                .borrow_mut()
+                .extend(message_queue.iter().copied());
+            program_state.done = true;
+        }
+
+        fn subscriptions(&self) -> MessageMask {
+            self.mask
+        }
+    }
+
+    #[test]
+    fn test_run_subscribed_filters_messages() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let message_queue = MessageQueue::new();
+        let odd_received = Rc::new(RefCell::new(Vec::new()));
+        let all_received = Rc::new(RefCell::new(Vec::new()));
+        let odd = odd_received.clone();
+        let all = all_received.clone();
+        let update_func =
+            move |program_state: &mut TestProgramState,
+                  message_queue: &mut MessageQueue<i32>,
+                  systems: Vec<Box<dyn System<TestProgramState, i32>>>| {
+                if program_state.done {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    for value in 1..=5 {
+                        message_queue.push(value);
+                    }
+                    vec![
+                        Box::new(SubscribedSystem {
+                            mask: MessageMask::kind(1),
+                            received: odd.clone(),
+                        }) as Box<dyn System<TestProgramState, i32>>,
+                        Box::new(SubscribedSystem {
+                            mask: MessageMask::ALL,
+                            received: all.clone(),
+                        }) as Box<dyn System<TestProgramState, i32>>,
+                    ]
+                } else {
+                    systems
+                }
+            };
+        // Kind 0 for even values, kind 1 for odd values.
+        let kind_of = |message: &i32| (message % 2) as u32;
+
+        run_subscribed(program_state, message_queue, update_func, kind_of);
+
+        assert_eq!(*odd_received.borrow(), [1, 3, 5]);
+        assert_eq!(*all_received.borrow(), [1, 2, 3, 4, 5]);
+    }
+
+    struct OddConsumer;
+
+    impl System<TestProgramState, i32> for OddConsumer {
+        fn update(
+            &mut self,
+            _program_state: &mut TestProgramState,
+            message_queue: &mut MessageQueue<i32>,
+        ) {
+            message_queue.retain(|message| 3 == *message);
+        }
+
+        fn subscriptions(&self) -> MessageMask {
+            MessageMask::kind(1)
+        }
+    }
+
+    #[test]
+    fn test_update_subscribed_restores_hidden_messages() {
+        let mut program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let mut message_queue = MessageQueue::new();
+        for value in 1..=7 {
+            message_queue.push(value);
+        }
+        message_queue.next_tick();
+        let kind_of = |message: &i32| (message % 2) as u32;
+
+        update_subscribed(
+            &mut OddConsumer,
+            &mut program_state,
+            &mut message_queue,
+            &kind_of,
+        );
+
+        // The even messages go back to their original indices as far as the shorter tick
+        // allows, keeping their order.
+        assert_eq!(
+            message_queue.iter().copied().collect::<Vec<_>>(),
+            [3, 2, 4, 6]
+        );
+    }
+
+    struct NoopSystem;
+
+    impl System<i32, i32> for NoopSystem {
+        fn update(&mut self, _program_state: &mut i32, _message_queue: &mut MessageQueue<i32>) {}
+    }
+
+    struct IncrementSystem;
+
+    impl System<i32, i32> for IncrementSystem {
+        fn update(&mut self, program_state: &mut i32, _message_queue: &mut MessageQueue<i32>) {
+            *program_state += 1;
+        }
+    }
+
+    #[test]
+    fn test_run_with_state_observer_attributes_changes() {
+        let mut changes = Vec::new();
+        let update_func = |program_state: &mut i32,
+                           _message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            if 2 <= *program_state {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![
+                    Box::new(NoopSystem) as Box<dyn System<i32, i32>>,
+                    Box::new(IncrementSystem) as Box<dyn System<i32, i32>>,
+                    Box::new(NoopSystem) as Box<dyn System<i32, i32>>,
+                ]
+            } else {
+                systems
+            }
+        };
+        let observer = |tick: u64, system_index: usize, before: &i32, after: &i32| {
+            changes.push((tick, system_index, *before, *after));
+        };
+
+        run_with_state_observer(0, MessageQueue::new(), update_func, observer);
+
+        assert_eq!(changes, [(1, 1, 0, 1), (2, 1, 1, 2)]);
+    }
+
+    // Pushes twice as many messages as it receives, plus one.
+    struct FloodSystem;
+
+    impl System<i32, i32> for FloodSystem {
+        fn update(&mut self, program_state: &mut i32, message_queue: &mut MessageQueue<i32>) {
+            let count = message_queue.iter().count() * 2 + 1;
+            for value in 0..count {
+                message_queue.push(value as i32);
+            }
+            *program_state += 1;
+        }
+    }
+
+    fn flood_update_func(
+        program_state: &mut i32,
+        _message_queue: &mut MessageQueue<i32>,
+        systems: Vec<Box<dyn System<i32, i32>>>,
+    ) -> Vec<Box<dyn System<i32, i32>>> {
+        if 4 <= *program_state {
+            Vec::new()
+        } else if systems.is_empty() {
+            vec![Box::new(FloodSystem) as Box<dyn System<i32, i32>>]
+        } else {
+            systems
+        }
+    }
+
+    #[test]
+    fn test_run_with_queue_limit_raises_alarm() {
+        let mut alarms = Vec::new();
+        let limit = QueueLimit {
+            high_water_mark: 4,
+            policy: OverflowPolicy::Keep,
+        };
+
+        run_with_queue_limit(
+            0,
+            MessageQueue::new(),
+            flood_update_func,
+            limit,
+            |tick, size| alarms.push((tick, size)),
+        );
+
+        // Queue sizes before ticks 2, 3 and 4 are 1, 3 and 7.
+        assert_eq!(alarms, [(4, 7)]);
+    }
+
+    #[test]
+    fn test_run_with_queue_limit_drops_overflow() {
+        let mut alarms = Vec::new();
+        let limit = QueueLimit {
+            high_water_mark: 2,
+            policy: OverflowPolicy::DropNewest,
+        };
+
+        run_with_queue_limit(
+            0,
+            MessageQueue::new(),
+            flood_update_func,
+            limit,
+            |tick, size| alarms.push((tick, size)),
+        );
+
+        // Trimming to two messages keeps the production at five per tick.
+        assert_eq!(alarms, [(3, 3), (4, 5)]);
+    }
+
+    // Records its name in the state each time it runs.
+    struct NamedSystem(&'static str);
+
+    impl System<Vec<&'static str>, ()> for NamedSystem {
+        fn update(
+            &mut self,
+            program_state: &mut Vec<&'static str>,
+            _message_queue: &mut MessageQueue<()>,
+        ) {
+            program_state.push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_run_composed() {
+        // Runs the sensor system for the first two ticks.
+        let sensors: BoxedUpdateFunc<Vec<&'static str>, ()> = Box::new(
+            |program_state: &mut Vec<&'static str>,
+             _message_queue: &mut MessageQueue<()>,
+             systems: Vec<Box<dyn System<Vec<&'static str>, ()>>>| {
+                let runs = program_state
+                    .iter()
+                    .filter(|name| **name == "sensor")
+                    .count();
+                if 2 <= runs {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    vec![Box::new(NamedSystem("sensor")) as Box<dyn System<_, _>>]
+                } else {
+                    systems
+                }
+            },
+        );
+        // Runs the display system for the first three ticks.
+        let display: BoxedUpdateFunc<Vec<&'static str>, ()> = Box::new(
+            |program_state: &mut Vec<&'static str>,
+             _message_queue: &mut MessageQueue<()>,
+             systems: Vec<Box<dyn System<Vec<&'static str>, ()>>>| {
+                let runs = program_state
+                    .iter()
+                    .filter(|name| **name == "display")
+                    .count();
+                if 3 <= runs {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    vec![Box::new(NamedSystem("display")) as Box<dyn System<_, _>>]
+                } else {
+                    systems
+                }
+            },
+        );
+
+        // Keeps a copy of the final state so the test can inspect it.
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let recorder_trace = trace.clone();
+        let recorder: BoxedUpdateFunc<Vec<&'static str>, ()> = Box::new(
+            move |program_state: &mut Vec<&'static str>,
+                  _message_queue: &mut MessageQueue<()>,
+                  _systems: Vec<Box<dyn System<Vec<&'static str>, ()>>>| {
+                recorder_trace.borrow_mut().clone_from(program_state);
+                Vec::new()
+            },
+        );
+
+        run_composed(
+            Vec::new(),
+            MessageQueue::new(),
+            vec![sensors, display, recorder],
+        );
+
+        assert_eq!(
+            *trace.borrow(),
+            ["sensor", "display", "sensor", "display", "display"]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum ControlMessage {
+        Suspend(&'static str),
+        Resume(&'static str),
+    }
+
+    // Counts its own updates in the state.
+    struct CounterSystem {
+        name: &'static str,
+        index: usize,
+    }
+
+    impl System<[u32; 2], ControlMessage> for CounterSystem {
+        fn update(
+            &mut self,
+            program_state: &mut [u32; 2],
+            _message_queue: &mut MessageQueue<ControlMessage>,
+        ) {
+            program_state[self.index] += 1;
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn control(message: &ControlMessage) -> Option<SystemControl<'_>> {
+        match message {
+            ControlMessage::Suspend(name) => Some(SystemControl::Suspend(name)),
+            ControlMessage::Resume(name) => Some(SystemControl::Resume(name)),
+        }
+    }
+
+    #[test]
+    fn test_run_suspendable() {
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let update_trace = trace.clone();
+        let mut tick = 0;
+        let update_func =
+            move |program_state: &mut [u32; 2],
+                  message_queue: &mut MessageQueue<ControlMessage>,
+                  systems: Vec<Box<dyn System<[u32; 2], ControlMessage>>>| {
+                update_trace.borrow_mut().push(*program_state);
+                tick += 1;
+                match tick {
+                    2 => message_queue.push(ControlMessage::Suspend("logger")),
+                    4 => message_queue.push(ControlMessage::Resume("logger")),
+                    _ => (),
+                }
+                if 6 <= tick {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    vec![
+                        Box::new(CounterSystem {
+                            name: "logger",
+                            index: 0,
+                        }) as Box<dyn System<[u32; 2], ControlMessage>>,
+                        Box::new(CounterSystem {
+                            name: "sensor",
+                            index: 1,
+                        }) as Box<dyn System<[u32; 2], ControlMessage>>,
+                    ]
+                } else {
+                    systems
+                }
+            };
+        run_suspendable([0, 0], MessageQueue::new(), update_func, control);
+
+        // The logger skips ticks 2 and 3 while suspended, the sensor never stops.
+        assert_eq!(
+            *trace.borrow(),
+            [[0, 0], [1, 1], [1, 2], [1, 3], [2, 4], [3, 5]]
+        );
+    }
+
+    #[test]
+    fn test_run_with_exit_code() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
 
-    while !systems.is_empty() {
-        message_queue.next_tick();
-        for system in systems.iter_mut() {
-            system.update(&mut program_state, &mut message_queue);
+        // The sum doubles each tick: 1, 2, 4, 8, 16.
+        let exit_code = run_with_exit_code(
+            program_state,
+            MessageQueue::new(),
+            test_update_func,
+            |program_state| program_state.sum,
+        );
+        assert_eq!(exit_code, 16);
+    }
+
+    #[test]
+    fn test_run_swappable() {
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let cruise_trace = trace.clone();
+        // Cruise scheduling: only the display runs, for two ticks.
+        let cruise: SwappableUpdateFunc<Vec<&'static str>, ()> = Box::new(
+            move |program_state: &mut Vec<&'static str>,
+                  _message_queue: &mut MessageQueue<()>,
+                  _systems: Vec<Box<dyn System<Vec<&'static str>, ()>>>| {
+                cruise_trace.borrow_mut().clone_from(program_state);
+                if 5 <= program_state.len() {
+                    UpdateControl::Continue(Vec::new())
+                } else {
+                    UpdateControl::Continue(vec![Box::new(NamedSystem("display"))])
+                }
+            },
+        );
+        // Startup scheduling: the sensor runs for three ticks, then hands over.
+        let mut cruise = Some(cruise);
+        let startup: SwappableUpdateFunc<Vec<&'static str>, ()> = Box::new(
+            move |program_state: &mut Vec<&'static str>,
+                  _message_queue: &mut MessageQueue<()>,
+                  systems: Vec<Box<dyn System<Vec<&'static str>, ()>>>| {
+                if 3 <= program_state.len() {
+                    let cruise = cruise.take().expect("Cruise scheduling expected.");
+                    UpdateControl::Swap(vec![Box::new(NamedSystem("display"))], cruise)
+                } else if systems.is_empty() {
+                    UpdateControl::Continue(vec![Box::new(NamedSystem("sensor"))])
+                } else {
+                    UpdateControl::Continue(systems)
+                }
+            },
+        );
+
+        run_swappable(Vec::new(), MessageQueue::new(), startup);
+
+        assert_eq!(
+            *trace.borrow(),
+            ["sensor", "sensor", "sensor", "display", "display"]
+        );
+    }
+
+    type IntSystems = Vec<Box<dyn System<i32, i32>>>;
+
+    // Pushes exactly as many messages as it hints, recording the capacity it found.
+    struct HintedSystem {
+        hint: usize,
+        capacities: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl System<i32, i32> for HintedSystem {
+        fn update(&mut self, program_state: &mut i32, message_queue: &mut MessageQueue<i32>) {
+            self.capacities
+                .borrow_mut()
+                .push(message_queue.next_tick_queue.capacity());
+            for _ in 0..self.hint {
+                message_queue.push(*program_state);
+            }
+            *program_state += 1;
+        }
+
+        fn push_hint(&self) -> usize {
+            self.hint
         }
-        systems = update(&mut program_state, &mut message_queue, systems);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // Runs two `HintedSystem`s with hints of 20 and 30 for five ticks, recording the capacity
+    // each found in `capacities`.
+    fn hinted_update_func(
+        capacities: &Rc<RefCell<Vec<usize>>>,
+    ) -> impl FnMut(&mut i32, &mut MessageQueue<i32>, IntSystems) -> IntSystems {
+        let capacities = capacities.clone();
+        move |program_state, _message_queue, systems| {
+            if 10 <= *program_state {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![
+                    Box::new(HintedSystem {
+                        hint: 20,
+                        capacities: capacities.clone(),
+                    }) as Box<dyn System<i32, i32>>,
+                    Box::new(HintedSystem {
+                        hint: 30,
+                        capacities: capacities.clone(),
+                    }) as Box<dyn System<i32, i32>>,
+                ]
+            } else {
+                systems
+            }
+        }
+    }
 
-    struct TestProgramState {
-        done: bool,
-        sum: i32,
+    // The first system of each tick finds room for both systems' messages.
+    fn assert_hints_reserved(capacities: &Rc<RefCell<Vec<usize>>>) {
+        assert_eq!(capacities.borrow().len(), 10);
+        for capacity in capacities.borrow().iter().step_by(2) {
+            assert!(50 <= *capacity);
+        }
+        capacities.borrow_mut().clear();
     }
 
-    struct TestSystem;
+    #[test]
+    fn test_run_reserves_push_hints() {
+        let capacities = Rc::new(RefCell::new(Vec::new()));
 
-    impl System<TestProgramState, i32> for TestSystem {
+        run(0, MessageQueue::new(), hinted_update_func(&capacities));
+        assert_hints_reserved(&capacities);
+    }
+
+    #[test]
+    fn test_run_variants_reserve_push_hints() {
+        let capacities = Rc::new(RefCell::new(Vec::new()));
+
+        run_subscribed(
+            0,
+            MessageQueue::new(),
+            hinted_update_func(&capacities),
+            |_| 0,
+        );
+        assert_hints_reserved(&capacities);
+
+        run_with_tick_context(0, MessageQueue::new(), hinted_update_func(&capacities));
+        assert_hints_reserved(&capacities);
+
+        run_with_middleware(
+            0,
+            MessageQueue::new(),
+            hinted_update_func(&capacities),
+            |_| (),
+        );
+        assert_hints_reserved(&capacities);
+
+        run_with_message_limit(
+            0,
+            MessageQueue::new(),
+            hinted_update_func(&capacities),
+            u64::MAX,
+        );
+        assert_hints_reserved(&capacities);
+
+        let mut update = hinted_update_func(&capacities);
+        run_with_context(
+            0,
+            MessageQueue::new(),
+            |program_state, message_queue, systems, _context: &RunContext| {
+                update(program_state, message_queue, systems)
+            },
+        );
+        assert_hints_reserved(&capacities);
+    }
+
+    // The messages seen on each tick.
+    type Sightings = Vec<Vec<char>>;
+
+    // Records the messages it sees each tick without consuming them.
+    struct WatchSystem;
+
+    impl System<Sightings, Expiring<char>> for WatchSystem {
         fn update(
             &mut self,
-            program_state: &mut TestProgramState,
-            message_queue: &mut MessageQueue<i32>,
+            program_state: &mut Sightings,
+            message_queue: &mut MessageQueue<Expiring<char>>,
         ) {
-            for message_value in message_queue.iter() {
-                program_state.sum += message_value;
+            program_state.push(
+                message_queue
+                    .iter()
+                    .map(|expiring| expiring.message)
+                    .collect(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_expiring() {
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let update_trace = trace.clone();
+        let update_func =
+            move |program_state: &mut Sightings,
+                  message_queue: &mut MessageQueue<Expiring<char>>,
+                  systems: Vec<Box<dyn System<Sightings, Expiring<char>>>>| {
+                update_trace.borrow_mut().clone_from(program_state);
+                if 4 <= program_state.len() {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    message_queue.batch_iter(1);
+                    message_queue.push_with_ttl('a', 3);
+                    message_queue.push_with_ttl('b', 1);
+                    message_queue.push_with_ttl('c', 3);
+                    vec![Box::new(WatchSystem) as Box<dyn System<_, _>>]
+                } else {
+                    systems
+                }
+            };
+
+        run_expiring(Vec::new(), MessageQueue::new(), update_func);
+
+        assert_eq!(*trace.borrow(), [vec!['a'], vec!['c'], vec![], vec![]]);
+    }
+
+    #[test]
+    fn test_run_collecting() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+
+        // Delivered messages are 1, 1, 2, 4, 8; only the even ones are logged.
+        let (program_state, lines) = run_collecting(
+            program_state,
+            MessageQueue::new(),
+            test_update_func,
+            |message| {
+                if message % 2 == 0 {
+                    Some(format!("sum = {}", message))
+                } else {
+                    None
+                }
+            },
+        );
+        assert_eq!(program_state.sum, 16);
+        assert_eq!(lines, ["sum = 2", "sum = 4", "sum = 8"]);
+    }
+
+    #[test]
+    fn test_run_with_drain() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let drained = RefCell::new(Vec::new());
+
+        // The last tick pushes 16, which no system is left to see.
+        run_with_drain(
+            program_state,
+            MessageQueue::new(),
+            test_update_func,
+            |_program_state, message_queue| {
+                drained.borrow_mut().extend(message_queue.iter().copied());
+            },
+        );
+        assert_eq!(*drained.borrow(), [16]);
+    }
+
+    // A panicking system never returns under `panic = "abort"`, so the test stands in for the
+    // panic handler and calls the hook from inside a system.
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_run_with_abort_hook() {
+        use core::sync::atomic::AtomicBool;
+
+        static TRIPPED: AtomicBool = AtomicBool::new(false);
+        static INNER_TRIPPED: AtomicBool = AtomicBool::new(false);
+        static OUTER_HOOK: AbortHook = AbortHook::new(trip);
+        static INNER_HOOK: AbortHook = AbortHook::new(trip_inner);
+
+        fn trip() {
+            TRIPPED.store(true, Ordering::SeqCst);
+        }
+
+        fn trip_inner() {
+            INNER_TRIPPED.store(true, Ordering::SeqCst);
+        }
+
+        struct FaultingSystem;
+
+        impl System<bool, ()> for FaultingSystem {
+            fn update(&mut self, done: &mut bool, _message_queue: &mut MessageQueue<()>) {
+                call_abort_hook();
+                *done = true;
             }
-            message_queue.push(program_state.sum);
-            if 10 < program_state.sum {
-                program_state.done = true;
+        }
+
+        let update_func = |done: &mut bool,
+                           _message_queue: &mut MessageQueue<()>,
+                           systems: Vec<Box<dyn System<bool, ()>>>| {
+            if *done {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![Box::new(FaultingSystem) as Box<dyn System<bool, ()>>]
+            } else {
+                systems
+            }
+        };
+        run_with_abort_hook(false, MessageQueue::new(), update_func, &OUTER_HOOK);
+
+        assert!(TRIPPED.load(Ordering::SeqCst));
+        // Once the loop is over the hook is no longer registered.
+        TRIPPED.store(false, Ordering::SeqCst);
+        call_abort_hook();
+        assert!(!TRIPPED.load(Ordering::SeqCst));
+
+        // A nested loop installs its own hook and restores the outer one when it ends.
+
+        struct NestingSystem;
+
+        impl System<bool, ()> for NestingSystem {
+            fn update(&mut self, done: &mut bool, _message_queue: &mut MessageQueue<()>) {
+                let update_func =
+                    |done: &mut bool,
+                     _message_queue: &mut MessageQueue<()>,
+                     systems: Vec<Box<dyn System<bool, ()>>>| {
+                        if *done {
+                            Vec::new()
+                        } else if systems.is_empty() {
+                            vec![Box::new(FaultingSystem) as Box<dyn System<bool, ()>>]
+                        } else {
+                            systems
+                        }
+                    };
+                run_with_abort_hook(false, MessageQueue::new(), update_func, &INNER_HOOK);
+                call_abort_hook();
+                *done = true;
+            }
+        }
+
+        let update_func = |done: &mut bool,
+                           _message_queue: &mut MessageQueue<()>,
+                           systems: Vec<Box<dyn System<bool, ()>>>| {
+            if *done {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![Box::new(NestingSystem) as Box<dyn System<bool, ()>>]
+            } else {
+                systems
+            }
+        };
+        run_with_abort_hook(false, MessageQueue::new(), update_func, &OUTER_HOOK);
+
+        assert!(INNER_TRIPPED.load(Ordering::SeqCst));
+        assert!(TRIPPED.load(Ordering::SeqCst));
+    }
+
+    // Consumes every message, adding it to the state.
+    struct ConsumingSystem;
+
+    impl System<i32, i32> for ConsumingSystem {
+        fn update(&mut self, program_state: &mut i32, message_queue: &mut MessageQueue<i32>) {
+            while let Some(message) = message_queue.pop_front_current() {
+                *program_state += message;
             }
         }
     }
 
     #[test]
-    fn test_run() {
+    fn test_run_with_idle() {
+        let mut idle_states = Vec::new();
+        let update_func = |_program_state: &mut i32,
+                           _message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            if systems.is_empty() {
+                vec![Box::new(ConsumingSystem) as Box<dyn System<i32, i32>>]
+            } else {
+                systems
+            }
+        };
+
+        run_with_idle(
+            0,
+            MessageQueue::new(),
+            update_func,
+            |program_state, message_queue| {
+                idle_states.push(*program_state);
+                if idle_states.len() < 3 {
+                    message_queue.push(10);
+                    true
+                } else {
+                    false
+                }
+            },
+        );
+
+        assert_eq!(idle_states, [0, 10, 20]);
+    }
+
+    #[test]
+    fn test_run_cooperative() {
         let program_state = TestProgramState {
             done: false,
             sum: 0,
         };
-        let message_queue = MessageQueue::new();
+
+        let mut polls = 0;
+        let mut poll = run_cooperative(CooperativeRun::new(
+            program_state,
+            MessageQueue::new(),
+            test_update_func,
+        ));
+        let program_state = loop {
+            polls += 1;
+            match poll {
+                RunPoll::Ready(program_state) => break program_state,
+                RunPoll::Pending(run) => {
+                    assert!(run.program_state().sum < 16);
+                    poll = run_cooperative(run);
+                }
+            }
+        };
+
+        // One call per tick: the sum doubles from 1 to 16 over five ticks.
+        assert_eq!(polls, 5);
+        assert_eq!(program_state.sum, 16);
+    }
+
+    // Raises one event on the third poll, as if an interrupt had fired between ticks.
+    struct MockInterruptSource {
+        polls: u32,
+    }
+
+    impl InterruptSource<i32> for MockInterruptSource {
+        fn poll(&mut self) -> Option<i32> {
+            self.polls += 1;
+            if 3 == self.polls {
+                Some(100)
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_with_interrupts() {
+        let mut trace = Vec::new();
+        let update_func = |program_state: &mut i32,
+                           _message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            trace.push(*program_state);
+            if 100 <= *program_state || 5 <= trace.len() {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![Box::new(ConsumingSystem) as Box<dyn System<i32, i32>>]
+            } else {
+                systems
+            }
+        };
+
+        run_with_interrupts(
+            0,
+            MessageQueue::new(),
+            update_func,
+            MockInterruptSource { polls: 0 },
+        );
+
+        // Each quiet tick takes one poll, so the event arrives and is consumed in the third tick.
+        assert_eq!(trace, [0, 0, 0, 100]);
+    }
+
+    // Returns scripted readings, one per call.
+    struct ScriptedClock {
+        readings_ms: Vec<u64>,
+    }
+
+    impl Clock for ScriptedClock {
+        fn now_ms(&mut self) -> u64 {
+            self.readings_ms.remove(0)
+        }
+    }
+
+    #[test]
+    fn test_run_with_histogram() {
+        let update_func = |program_state: &mut i32,
+                           _message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            if 5 <= *program_state {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![Box::new(IncrementSystem) as Box<dyn System<i32, i32>>]
+            } else {
+                systems
+            }
+        };
+        // Five ticks lasting 1, 2, 7, 1 and 25 ms.
+        let mut clock = ScriptedClock {
+            readings_ms: vec![0, 1, 3, 10, 11, 36],
+        };
+        let mut histogram = LatencyHistogram::new(&[1, 5, 10]);
+
+        run_with_histogram(
+            0,
+            MessageQueue::new(),
+            update_func,
+            &mut clock,
+            &mut histogram,
+        );
+
+        assert_eq!(histogram.counts(), [2, 1, 1, 1]);
+        assert_eq!(histogram.max_ms(), 25);
+        assert_eq!(histogram.ticks(), 5);
+    }
+
+    #[test]
+    fn test_run_with_middleware() {
+        let update_func = |program_state: &mut i32,
+                           message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            if systems.is_empty() {
+                message_queue.push(1);
+                message_queue.push(-50);
+                message_queue.push(2);
+                vec![Box::new(ConsumingSystem) as Box<dyn System<i32, i32>>]
+            } else {
+                assert_eq!(*program_state, 3);
+                Vec::new()
+            }
+        };
+
+        // The negative message never reaches the consuming system.
+        run_with_middleware(0, MessageQueue::new(), update_func, |message_queue| {
+            message_queue
+                .current_tick_queue
+                .retain(|message| 0 <= *message)
+        });
+    }
+
+    #[test]
+    fn test_run_with_middleware_capturing_state() {
+        let update_func = |program_state: &mut i32,
+                           message_queue: &mut MessageQueue<i32>,
+                           systems: Vec<Box<dyn System<i32, i32>>>| {
+            if 10 <= *program_state {
+                Vec::new()
+            } else {
+                message_queue.push(*program_state + 1);
+                if systems.is_empty() {
+                    vec![Box::new(ConsumingSystem) as Box<dyn System<i32, i32>>]
+                } else {
+                    systems
+                }
+            }
+        };
+        let mut seen = Vec::new();
+        let mut ticks = 0;
+
+        run_with_middleware(0, MessageQueue::new(), update_func, |message_queue| {
+            ticks += 1;
+            seen.extend(message_queue.iter().copied());
+        });
+
+        assert_eq!(ticks, 4);
+        assert_eq!(seen, [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_run_with_on_exit() {
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let mut exits = Vec::new();
+
+        // The last tick leaves its pushed sum behind.
+        run_with_on_exit(
+            program_state,
+            MessageQueue::new(),
+            test_update_func,
+            |program_state, reason| exits.push((program_state.sum, reason)),
+        );
+        assert_eq!(exits, [(16, ExitReason::PendingMessages(1))]);
+
+        let mut exits = Vec::new();
+        run_with_on_exit(
+            0,
+            MessageQueue::new(),
+            |_program_state: &mut i32,
+             _message_queue: &mut MessageQueue<i32>,
+             _systems: Vec<Box<dyn System<i32, i32>>>| Vec::new(),
+            |program_state, reason| exits.push((*program_state, reason)),
+        );
+        assert_eq!(exits, [(0, ExitReason::Finished)]);
+    }
+
+    #[test]
+    fn test_run_with_message_limit() {
+        let mut updates = 0;
         let update_func =
-            |program_state: &mut TestProgramState,
+            |_program_state: &mut TestProgramState,
              message_queue: &mut MessageQueue<i32>,
              systems: Vec<Box<dyn System<TestProgramState, i32>>>| {
-                if program_state.done {
-                    // return empty vec to exit
-                    Vec::new()
-                } else if systems.is_empty() {
-                    // push startup messages
+                updates += 1;
+                if systems.is_empty() {
                     message_queue.push(1);
-                    // initialize systems vec
                     vec![Box::new(TestSystem) as Box<dyn System<TestProgramState, i32>>]
                 } else {
+                    // Never finishes on its own.
                     systems
                 }
             };
+        let program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
 
-        run(program_state, message_queue, update_func);
+        // One message per tick, so the limit is reached in the third tick.
+        let reason = run_with_message_limit(program_state, MessageQueue::new(), update_func, 3);
+        assert_eq!(reason, ExitReason::MessageLimit);
+        // The initial update plus one after each of the first two ticks.
+        assert_eq!(updates, 3);
+    }
+
+    // Records the tick it is updated in; plain updates record zero.
+    struct TickRecordingSystem;
+
+    impl System<Vec<u64>, ()> for TickRecordingSystem {
+        fn update(&mut self, ticks: &mut Vec<u64>, _message_queue: &mut MessageQueue<()>) {
+            ticks.push(0);
+        }
+
+        fn update_ctx(
+            &mut self,
+            ctx: &TickContext,
+            ticks: &mut Vec<u64>,
+            _message_queue: &mut MessageQueue<()>,
+        ) {
+            ticks.push(ctx.tick);
+        }
+    }
+
+    #[test]
+    fn test_run_with_tick_context() {
+        let mut trace = Vec::new();
+        let update_func = |ticks: &mut Vec<u64>,
+                           _message_queue: &mut MessageQueue<()>,
+                           systems: Vec<Box<dyn System<Vec<u64>, ()>>>| {
+            trace.clone_from(ticks);
+            if 4 <= ticks.len() {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![Box::new(TickRecordingSystem) as Box<dyn System<Vec<u64>, ()>>]
+            } else {
+                systems
+            }
+        };
+
+        run_with_tick_context(Vec::new(), MessageQueue::new(), update_func);
+
+        assert_eq!(trace, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_run_borrowed() {
+        let mut program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+
+        run_borrowed(&mut program_state, MessageQueue::new(), test_update_func);
+
+        assert!(program_state.done);
+        assert_eq!(program_state.sum, 16);
+    }
+
+    // A clock the systems under test can advance, standing in for real work taking time.
+    struct SharedClock(Rc<RefCell<ManualClock>>);
+
+    impl Clock for SharedClock {
+        fn now_ms(&mut self) -> u64 {
+            self.0.borrow_mut().now_ms()
+        }
+    }
+
+    // Records the tick it was updated in under its name, taking `cost_ms` of clock time.
+    struct PacedSystem {
+        name: &'static str,
+        cost_ms: u64,
+        clock: Rc<RefCell<ManualClock>>,
+        updates: Rc<RefCell<Vec<(u64, &'static str)>>>,
+    }
+
+    impl System<(), ()> for PacedSystem {
+        fn update(&mut self, _program_state: &mut (), message_queue: &mut MessageQueue<()>) {
+            self.updates
+                .borrow_mut()
+                .push((message_queue.tick(), self.name));
+            self.clock.borrow_mut().advance(self.cost_ms);
+        }
+    }
+
+    #[test]
+    fn test_run_paced_defers_systems_over_budget() {
+        let clock = Rc::new(RefCell::new(ManualClock::new(0)));
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let system = |name, cost_ms| {
+            Box::new(PacedSystem {
+                name,
+                cost_ms,
+                clock: clock.clone(),
+                updates: updates.clone(),
+            }) as Box<dyn System<(), ()>>
+        };
+        let update_func = |_program_state: &mut (),
+                           message_queue: &mut MessageQueue<()>,
+                           systems: Vec<Box<dyn System<(), ()>>>| {
+            if 3 <= message_queue.tick() {
+                Vec::new()
+            } else if systems.is_empty() {
+                vec![system("slow", 8), system("fast", 0)]
+            } else {
+                systems
+            }
+        };
+
+        run_paced(
+            (),
+            MessageQueue::new(),
+            update_func,
+            &mut SharedClock(clock.clone()),
+            5,
+        );
+
+        // The slow system uses up the first tick's budget, so the fast one runs first in the
+        // second tick. In the third tick it is deferred again.
+        assert_eq!(
+            *updates.borrow(),
+            [(1, "slow"), (2, "fast"), (2, "slow"), (3, "slow")]
+        );
+    }
+
+    // Tries to push five messages every tick, counting the accepted and rejected ones.
+    struct TryPushSystem;
+
+    impl System<(u32, u32), i32> for TryPushSystem {
+        fn update(&mut self, counts: &mut (u32, u32), message_queue: &mut MessageQueue<i32>) {
+            for value in 0..5 {
+                match message_queue.try_push(value) {
+                    Ok(()) => counts.0 += 1,
+                    Err(rejected) => {
+                        assert_eq!(rejected, value);
+                        counts.1 += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_with_bounded_queue() {
+        let update_func =
+            |_counts: &mut (u32, u32),
+             message_queue: &mut MessageQueue<i32>,
+             systems: Vec<Box<dyn System<(u32, u32), i32>>>| {
+                if 3 <= message_queue.tick() {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    vec![Box::new(TryPushSystem) as Box<dyn System<(u32, u32), i32>>]
+                } else {
+                    assert!(message_queue.next_len() <= 3);
+                    systems
+                }
+            };
+        let mut counts = (0, 0);
+
+        run_borrowed(&mut counts, MessageQueue::with_limit(3), update_func);
+
+        assert_eq!(counts, (9, 6));
     }
 }
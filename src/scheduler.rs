@@ -0,0 +1,197 @@
+// src/scheduler.rs
+
+// The `scheduler.rs` module provides `GraphScheduler`, a helper for building the system list
+// that an update closure hands to `run` when the order of systems matters.
+
+// - Named Systems: Systems are registered under a name. Ordering constraints are declared
+//   between names with `depends_on`, meaning the dependency is updated earlier in the tick.
+
+// - Validation: `build` orders the systems topologically, keeping registration order wherever
+//   the constraints allow it. Unknown names and dependency cycles are reported as a
+//   `ScheduleError` instead of producing a silently wrong order.
+
+// - Visualization: `to_dot` renders the dependency graph in Graphviz DOT format, which is handy
+//   for documentation and for debugging complex system wiring.
+
+use crate::system::System;
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    // A dependency refers to a name that was never registered.
+    UnknownSystem(&'static str),
+    // The named systems depend on each other in a cycle and cannot be ordered.
+    Cycle(Vec<&'static str>),
+}
+
+pub struct GraphScheduler<ProgramState, Message> {
+    names: Vec<&'static str>,
+    systems: Vec<Box<dyn System<ProgramState, Message>>>,
+    // (dependent, dependency) pairs.
+    dependencies: Vec<(&'static str, &'static str)>,
+}
+
+impl<ProgramState, Message> Default for GraphScheduler<ProgramState, Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ProgramState, Message> GraphScheduler<ProgramState, Message> {
+    pub fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            systems: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        name: &'static str,
+        system: Box<dyn System<ProgramState, Message>>,
+    ) -> &mut Self {
+        self.names.push(name);
+        self.systems.push(system);
+        self
+    }
+
+    // Declares that `name` must be updated after `dependency` within a tick.
+    pub fn depends_on(&mut self, name: &'static str, dependency: &'static str) -> &mut Self {
+        self.dependencies.push((name, dependency));
+        self
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph systems {\n");
+        for name in self.names.iter() {
+            dot.push_str(&format!("    \"{}\";\n", name));
+        }
+        for (name, dependency) in self.dependencies.iter() {
+            dot.push_str(&format!("    \"{}\" -> \"{}\";\n", dependency, name));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn build(self) -> Result<Vec<Box<dyn System<ProgramState, Message>>>, ScheduleError> {
+        let index_of = |name: &'static str| {
+            self.names
+                .iter()
+                .position(|system_name| *system_name == name)
+                .ok_or(ScheduleError::UnknownSystem(name))
+        };
+        let mut edges = Vec::with_capacity(self.dependencies.len());
+        for (name, dependency) in self.dependencies.iter() {
+            edges.push((index_of(name)?, index_of(dependency)?));
+        }
+
+        // Kahn's algorithm, always picking the earliest registered ready system.
+        let mut pending: Vec<usize> = (0..self.systems.len())
+            .map(|index| {
+                edges
+                    .iter()
+                    .filter(|(dependent, _)| *dependent == index)
+                    .count()
+            })
+            .collect();
+        let mut scheduled = Vec::with_capacity(self.systems.len());
+        let mut done = vec![false; self.systems.len()];
+        while let Some(index) =
+            (0..pending.len()).find(|index| !done[*index] && 0 == pending[*index])
+        {
+            done[index] = true;
+            scheduled.push(index);
+            for (dependent, _) in edges.iter().filter(|(_, dependency)| *dependency == index) {
+                pending[*dependent] -= 1;
+            }
+        }
+
+        if scheduled.len() < self.systems.len() {
+            let cycle = self
+                .names
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !done[*index])
+                .map(|(_, name)| *name)
+                .collect();
+            return Err(ScheduleError::Cycle(cycle));
+        }
+
+        let mut systems: Vec<Option<Box<dyn System<ProgramState, Message>>>> =
+            self.systems.into_iter().map(Some).collect();
+        Ok(scheduled
+            .into_iter()
+            .filter_map(|index| systems[index].take())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_queue::MessageQueue;
+
+    struct NamedSystem(&'static str);
+
+    impl System<Vec<&'static str>, ()> for NamedSystem {
+        fn update(
+            &mut self,
+            program_state: &mut Vec<&'static str>,
+            _messages: &mut MessageQueue<()>,
+        ) {
+            program_state.push(self.0);
+        }
+    }
+
+    fn scheduler() -> GraphScheduler<Vec<&'static str>, ()> {
+        let mut scheduler = GraphScheduler::new();
+        scheduler
+            .add("output", Box::new(NamedSystem("output")))
+            .add("calculator", Box::new(NamedSystem("calculator")))
+            .add("input", Box::new(NamedSystem("input")))
+            .depends_on("calculator", "input")
+            .depends_on("output", "calculator");
+        scheduler
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = scheduler().to_dot();
+        assert!(dot.starts_with("digraph systems {"));
+        assert!(dot.contains("\"input\" -> \"calculator\";"));
+        assert!(dot.contains("\"calculator\" -> \"output\";"));
+        assert!(!dot.contains("\"output\" -> "));
+    }
+
+    #[test]
+    fn test_build_orders_dependencies_first() {
+        let mut systems = scheduler().build().unwrap();
+        let mut program_state = Vec::new();
+        let mut messages = MessageQueue::new();
+        for system in systems.iter_mut() {
+            system.update(&mut program_state, &mut messages);
+        }
+        assert_eq!(program_state, ["input", "calculator", "output"]);
+    }
+
+    #[test]
+    fn test_build_detects_cycles() {
+        let mut scheduler = scheduler();
+        scheduler.depends_on("input", "output");
+        assert_eq!(
+            scheduler.build().err(),
+            Some(ScheduleError::Cycle(vec!["output", "calculator", "input"]))
+        );
+    }
+
+    #[test]
+    fn test_build_detects_unknown_systems() {
+        let mut scheduler = scheduler();
+        scheduler.depends_on("input", "sensor");
+        assert_eq!(
+            scheduler.build().err(),
+            Some(ScheduleError::UnknownSystem("sensor"))
+        );
+    }
+}
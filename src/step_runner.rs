@@ -0,0 +1,155 @@
+// src/step_runner.rs
+
+// The `step_runner.rs` module provides `StepRunner`, the `run` loop turned inside out. Instead
+// of handing control to `run` until the program finishes, the caller owns a `StepRunner` and
+// decides when each tick happens, which is what an external event loop, a GUI frame callback,
+// or a test that wants to look at intermediate state needs.
+
+// - Ownership: The runner owns the program state, the message queue, the update closure and
+//   the active systems. Accessors expose the state and the queue between steps.
+
+// - Stepping: Each call to `step` runs exactly one tick with the same semantics as `run`. The
+//   first call also performs the initial update that creates the systems. `step` returns false
+//   once the update closure has returned no systems, after which further calls do nothing.
+
+use crate::{message_queue::MessageQueue, run::BoxedUpdateFunc, system::System};
+use alloc::{boxed::Box, vec, vec::Vec};
+
+pub struct StepRunner<ProgramState, Message> {
+    program_state: ProgramState,
+    message_queue: MessageQueue<Message>,
+    update: BoxedUpdateFunc<ProgramState, Message>,
+    systems: Vec<Box<dyn System<ProgramState, Message>>>,
+    started: bool,
+}
+
+impl<ProgramState, Message> StepRunner<ProgramState, Message> {
+    pub fn new(
+        program_state: ProgramState,
+        message_queue: MessageQueue<Message>,
+        update: BoxedUpdateFunc<ProgramState, Message>,
+    ) -> Self {
+        Self {
+            program_state,
+            message_queue,
+            update,
+            systems: Vec::new(),
+            started: false,
+        }
+    }
+
+    // Runs one tick. Returns whether there is more work to do.
+    pub fn step(&mut self) -> bool {
+        if !self.started {
+            self.started = true;
+            self.systems = (self.update)(&mut self.program_state, &mut self.message_queue, vec![]);
+        }
+        if self.systems.is_empty() {
+            return false;
+        }
+
+        self.message_queue.next_tick();
+        self.message_queue
+            .reserve(self.systems.iter().map(|system| system.push_hint()).sum());
+        for system in self.systems.iter_mut() {
+            system.update(&mut self.program_state, &mut self.message_queue);
+        }
+        let systems = core::mem::take(&mut self.systems);
+        self.systems = (self.update)(&mut self.program_state, &mut self.message_queue, systems);
+        !self.systems.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.started && self.systems.is_empty()
+    }
+
+    pub fn system_count(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn program_state(&self) -> &ProgramState {
+        &self.program_state
+    }
+
+    pub fn program_state_mut(&mut self) -> &mut ProgramState {
+        &mut self.program_state
+    }
+
+    pub fn message_queue(&self) -> &MessageQueue<Message> {
+        &self.message_queue
+    }
+
+    pub fn message_queue_mut(&mut self) -> &mut MessageQueue<Message> {
+        &mut self.message_queue
+    }
+
+    pub fn into_parts(self) -> (ProgramState, MessageQueue<Message>) {
+        (self.program_state, self.message_queue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adds every message to the state and echoes the running total.
+    struct SumSystem;
+
+    impl System<i32, i32> for SumSystem {
+        fn update(&mut self, program_state: &mut i32, message_queue: &mut MessageQueue<i32>) {
+            for message in message_queue.iter() {
+                *program_state += message;
+            }
+            message_queue.push(*program_state);
+        }
+    }
+
+    fn update_func() -> BoxedUpdateFunc<i32, i32> {
+        Box::new(
+            |program_state: &mut i32,
+             message_queue: &mut MessageQueue<i32>,
+             systems: Vec<Box<dyn System<i32, i32>>>| {
+                if 10 < *program_state {
+                    Vec::new()
+                } else if systems.is_empty() {
+                    message_queue.push(1);
+                    vec![Box::new(SumSystem) as Box<dyn System<i32, i32>>]
+                } else {
+                    systems
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_step_runner() {
+        let mut runner = StepRunner::new(0, MessageQueue::new(), update_func());
+        assert!(!runner.is_finished());
+        assert_eq!(runner.system_count(), 0);
+
+        let mut states = Vec::new();
+        while runner.step() {
+            states.push(*runner.program_state());
+            assert_eq!(runner.system_count(), 1);
+        }
+        states.push(*runner.program_state());
+
+        assert_eq!(states, [1, 2, 4, 8, 16]);
+        assert!(runner.is_finished());
+        assert!(!runner.step());
+
+        let (program_state, message_queue) = runner.into_parts();
+        assert_eq!(program_state, 16);
+        assert_eq!(message_queue.peek_next(), Some(&16));
+    }
+
+    #[test]
+    fn test_step_runner_state_can_be_changed_between_steps() {
+        let mut runner = StepRunner::new(0, MessageQueue::new(), update_func());
+        assert!(runner.step());
+        *runner.program_state_mut() = 100;
+        runner.message_queue_mut().push(5);
+        assert!(!runner.step());
+        assert_eq!(*runner.program_state(), 106);
+    }
+}
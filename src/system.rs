@@ -26,9 +26,190 @@
 // or embedded environments where the Flight Brain project is typically deployed.
 
 use crate::message_queue::MessageQueue;
+use alloc::boxed::Box;
+
+// A set of message kinds a system wants to receive. What a kind is belongs to the program: it
+// supplies a mapping from messages to bit indices, usually one bit per enum variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageMask(u64);
+
+impl MessageMask {
+    pub const ALL: MessageMask = MessageMask(u64::MAX);
+    pub const NONE: MessageMask = MessageMask(0);
+
+    pub const fn kind(kind: u32) -> Self {
+        Self::NONE.with(kind)
+    }
+
+    // Panics if `kind` is 64 or more, since a mask only has room for 64 kinds.
+    pub const fn with(self, kind: u32) -> Self {
+        assert!(kind < u64::BITS, "Message kind out of range.");
+        MessageMask(self.0 | (1 << kind))
+    }
+
+    pub const fn contains(self, kind: u32) -> bool {
+        kind < u64::BITS && 0 != self.0 & (1 << kind)
+    }
+}
+
+// Per-tick information handed to systems by `run_with_tick_context`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickContext {
+    // The queue's tick counter: one during the first tick, increasing by one each tick.
+    pub tick: u64,
+}
 
 pub trait System<ProgramState, Message> {
     fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>);
+
+    // Called instead of `update` by `run_with_tick_context`. Systems that need the tick number
+    // override this rather than keeping a tick count in the program state.
+    fn update_ctx(
+        &mut self,
+        _ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        self.update(program_state, messages);
+    }
+
+    // The message kinds this system reads. `run_subscribed` hides all other current-tick
+    // messages from the system while it updates; the plain `run` ignores this.
+    fn subscriptions(&self) -> MessageMask {
+        MessageMask::ALL
+    }
+
+    // A name identifying the system to runtime controls such as `run_suspendable`. Unnamed
+    // systems return an empty string.
+    fn name(&self) -> &str {
+        ""
+    }
+
+    // An upper bound on the number of messages a single update pushes. `run` and its variants
+    // reserve room for the sum of all hints before each tick, so pushing does not reallocate
+    // mid-tick.
+    fn push_hint(&self) -> usize {
+        0
+    }
+}
+
+// A system whose internal state can be inspected from the outside, mainly by tests. The
+// snapshot is a copy, so systems can keep their fields private and still be asserted on.
+// Implementations choose whatever `Snapshot` type describes them best.
+pub trait StatefulSystem<ProgramState, Message>: System<ProgramState, Message> {
+    type Snapshot;
+
+    fn snapshot(&self) -> Self::Snapshot;
+}
+
+// Boxed systems, including `Box<dyn System>`, are systems themselves. This lets combinators
+// that are generic over `System` hold systems that have already been boxed.
+impl<ProgramState, Message, S> System<ProgramState, Message> for Box<S>
+where
+    S: System<ProgramState, Message> + ?Sized,
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        (**self).update(program_state, messages);
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        (**self).update_ctx(ctx, program_state, messages);
+    }
+
+    fn subscriptions(&self) -> MessageMask {
+        (**self).subscriptions()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn push_hint(&self) -> usize {
+        (**self).push_hint()
+    }
+}
+
+// Builds a system on demand, so systems that may never be scheduled do not take up memory
+// at startup. Closures returning a boxed system are factories too.
+pub trait SystemFactory<ProgramState, Message> {
+    fn create(&self) -> Box<dyn System<ProgramState, Message>>;
+}
+
+impl<ProgramState, Message, F> SystemFactory<ProgramState, Message> for F
+where
+    F: Fn() -> Box<dyn System<ProgramState, Message>>,
+{
+    fn create(&self) -> Box<dyn System<ProgramState, Message>> {
+        self()
+    }
+}
+
+// A stand-in that asks its factory for the real system on its first update and forwards to
+// it from then on. Scheduling a `LazySystem` in `run` therefore defers construction until the
+// system is first needed. Before that, it reports no name and no push hint.
+pub struct LazySystem<ProgramState, Message> {
+    factory: Box<dyn SystemFactory<ProgramState, Message>>,
+    system: Option<Box<dyn System<ProgramState, Message>>>,
+}
+
+impl<ProgramState, Message> LazySystem<ProgramState, Message> {
+    pub fn new(factory: Box<dyn SystemFactory<ProgramState, Message>>) -> Self {
+        Self {
+            factory,
+            system: None,
+        }
+    }
+
+    pub fn is_created(&self) -> bool {
+        self.system.is_some()
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for LazySystem<ProgramState, Message> {
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let factory = &self.factory;
+        self.system
+            .get_or_insert_with(|| factory.create())
+            .update(program_state, messages);
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        let factory = &self.factory;
+        self.system
+            .get_or_insert_with(|| factory.create())
+            .update_ctx(ctx, program_state, messages);
+    }
+
+    fn subscriptions(&self) -> MessageMask {
+        match &self.system {
+            Some(system) => system.subscriptions(),
+            None => MessageMask::ALL,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match &self.system {
+            Some(system) => system.name(),
+            None => "",
+        }
+    }
+
+    fn push_hint(&self) -> usize {
+        match &self.system {
+            Some(system) => system.push_hint(),
+            None => 0,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -91,4 +272,107 @@ mod tests {
         assert_eq!(message_queue.iter().count(), 1);
         assert_eq!(message_queue.iter().next(), Some(&30));
     }
+
+    #[test]
+    fn test_message_mask() {
+        let mask = MessageMask::kind(1).with(3);
+        assert!(!mask.contains(0));
+        assert!(mask.contains(1));
+        assert!(mask.contains(3));
+        assert!(!mask.contains(64));
+        assert!(MessageMask::ALL.contains(63));
+        assert!(!MessageMask::NONE.contains(0));
+        assert_eq!(TestSystem.subscriptions(), MessageMask::ALL);
+        assert_eq!(TestSystem.name(), "");
+        assert_eq!(TestSystem.push_hint(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_message_mask_kind_out_of_range() {
+        MessageMask::kind(64);
+    }
+
+    struct CountingSystem {
+        updates: u32,
+        messages_seen: usize,
+    }
+
+    impl System<TestProgramState, i32> for CountingSystem {
+        fn update(
+            &mut self,
+            _program_state: &mut TestProgramState,
+            messages: &mut MessageQueue<i32>,
+        ) {
+            self.updates += 1;
+            self.messages_seen += messages.iter().count();
+        }
+    }
+
+    impl StatefulSystem<TestProgramState, i32> for CountingSystem {
+        type Snapshot = (u32, usize);
+
+        fn snapshot(&self) -> Self::Snapshot {
+            (self.updates, self.messages_seen)
+        }
+    }
+
+    #[test]
+    fn test_stateful_system_snapshot() {
+        let mut program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let mut message_queue = MessageQueue::new();
+        let mut counting_system = CountingSystem {
+            updates: 0,
+            messages_seen: 0,
+        };
+        assert_eq!(counting_system.snapshot(), (0, 0));
+
+        message_queue.push(1);
+        message_queue.push(2);
+        message_queue.next_tick();
+        counting_system.update(&mut program_state, &mut message_queue);
+        assert_eq!(counting_system.snapshot(), (1, 2));
+    }
+
+    #[test]
+    fn test_lazy_system_creates_on_first_update() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct CountingFactory {
+            created: Rc<Cell<u32>>,
+        }
+
+        impl SystemFactory<TestProgramState, i32> for CountingFactory {
+            fn create(&self) -> Box<dyn System<TestProgramState, i32>> {
+                self.created.set(self.created.get() + 1);
+                Box::new(TestSystem)
+            }
+        }
+
+        let created = Rc::new(Cell::new(0));
+        let mut program_state = TestProgramState {
+            done: false,
+            sum: 0,
+        };
+        let mut message_queue = MessageQueue::new();
+        let mut lazy_system = LazySystem::new(Box::new(CountingFactory {
+            created: created.clone(),
+        }));
+        assert_eq!(created.get(), 0);
+        assert!(!lazy_system.is_created());
+
+        message_queue.push(5);
+        message_queue.next_tick();
+        lazy_system.update(&mut program_state, &mut message_queue);
+        message_queue.next_tick();
+        lazy_system.update(&mut program_state, &mut message_queue);
+
+        assert_eq!(created.get(), 1);
+        assert!(lazy_system.is_created());
+        assert_eq!(program_state.sum, 10);
+    }
 }
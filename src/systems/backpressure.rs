@@ -0,0 +1,61 @@
+// src/systems/backpressure.rs
+
+// `BackpressureSystem` provides flow control. It looks at how many messages are queued for the
+// next tick and reports through `set_paused` whether the backlog exceeds the threshold, so
+// producer systems can check the flag in the program state and hold off until it clears. It
+// should be scheduled after the producers, once the next tick has been filled.
+
+use crate::{message_queue::MessageQueue, system::System};
+
+pub struct BackpressureSystem<ProgramState> {
+    threshold: usize,
+    set_paused: fn(&mut ProgramState, bool),
+}
+
+impl<ProgramState> BackpressureSystem<ProgramState> {
+    pub fn new(threshold: usize, set_paused: fn(&mut ProgramState, bool)) -> Self {
+        Self {
+            threshold,
+            set_paused,
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for BackpressureSystem<ProgramState> {
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        (self.set_paused)(program_state, self.threshold < messages.next_len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestProgramState {
+        paused: bool,
+    }
+
+    #[test]
+    fn test_pause_flag_follows_backlog() {
+        let mut system =
+            BackpressureSystem::new(2, |program_state: &mut TestProgramState, paused| {
+                program_state.paused = paused
+            });
+        let mut program_state = TestProgramState { paused: false };
+        let mut messages = MessageQueue::new();
+
+        messages.push(1);
+        messages.push(2);
+        system.update(&mut program_state, &mut messages);
+        assert!(!program_state.paused);
+
+        messages.push(3);
+        system.update(&mut program_state, &mut messages);
+        assert!(program_state.paused);
+
+        messages.next_tick();
+        messages.push(4);
+        system.update(&mut program_state, &mut messages);
+        assert!(!program_state.paused);
+    }
+}
@@ -0,0 +1,188 @@
+// src/systems/circuit_breaker.rs
+
+// `CircuitBreakerSystem` protects a flaky downstream resource. It counts failure messages, and
+// once `threshold` failures have been seen within `window` ticks the breaker opens. While open,
+// every guarded command is removed from the current tick before downstream systems see it, and
+// the optional rejection message is pushed in its place. After `cooldown` ticks the breaker
+// closes again and commands flow normally.
+
+use crate::{
+    message_queue::MessageQueue,
+    system::{StatefulSystem, System},
+};
+use alloc::{collections::VecDeque, vec::Vec};
+
+pub struct CircuitBreakerSystem<Message> {
+    threshold: usize,
+    window: u64,
+    cooldown: u64,
+    is_failure: fn(&Message) -> bool,
+    is_guarded: fn(&Message) -> bool,
+    reject: fn(&Message) -> Option<Message>,
+    tick: u64,
+    failure_ticks: VecDeque<u64>,
+    open_until: Option<u64>,
+}
+
+impl<Message> CircuitBreakerSystem<Message> {
+    pub fn new(
+        threshold: usize,
+        window: u64,
+        cooldown: u64,
+        is_failure: fn(&Message) -> bool,
+        is_guarded: fn(&Message) -> bool,
+        reject: fn(&Message) -> Option<Message>,
+    ) -> Self {
+        Self {
+            threshold,
+            window,
+            cooldown,
+            is_failure,
+            is_guarded,
+            reject,
+            tick: 0,
+            failure_ticks: VecDeque::new(),
+            open_until: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open_until.is_some()
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for CircuitBreakerSystem<Message> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if self.open_until.is_some_and(|open_until| open_until <= tick) {
+            self.open_until = None;
+        }
+
+        let failures = messages
+            .iter()
+            .filter(|message| (self.is_failure)(message))
+            .count();
+        self.failure_ticks
+            .extend(core::iter::repeat_n(tick, failures));
+        while self
+            .failure_ticks
+            .front()
+            .is_some_and(|failure_tick| self.window <= tick - failure_tick)
+        {
+            self.failure_ticks.pop_front();
+        }
+        if !self.is_open() && self.threshold <= self.failure_ticks.len() {
+            self.open_until = Some(tick + self.cooldown);
+            self.failure_ticks.clear();
+        }
+
+        if self.is_open() {
+            let mut rejected = Vec::new();
+            messages.current_tick_queue.retain(|message| {
+                if (self.is_guarded)(message) {
+                    rejected.push((self.reject)(message));
+                    false
+                } else {
+                    true
+                }
+            });
+            for rejection in rejected.into_iter().flatten() {
+                messages.push(rejection);
+            }
+        }
+    }
+}
+
+// Whether the breaker is open, and how many failures are currently counted against it.
+impl<ProgramState, Message> StatefulSystem<ProgramState, Message>
+    for CircuitBreakerSystem<Message>
+{
+    type Snapshot = (bool, usize);
+
+    fn snapshot(&self) -> (bool, usize) {
+        (self.is_open(), self.failure_ticks.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::test_support::tick;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Command(i32),
+        Failure,
+        Rejected(i32),
+    }
+
+    fn breaker() -> CircuitBreakerSystem<TestMessage> {
+        CircuitBreakerSystem::new(
+            2,
+            3,
+            2,
+            |message| matches!(message, TestMessage::Failure),
+            |message| matches!(message, TestMessage::Command(_)),
+            |message| match message {
+                TestMessage::Command(id) => Some(TestMessage::Rejected(*id)),
+                _ => None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let mut system = breaker();
+        let mut messages = MessageQueue::new();
+
+        let seen = tick(&mut system, &mut (), &mut messages, &[TestMessage::Failure]);
+        assert_eq!(seen, [TestMessage::Failure]);
+        assert!(!system.is_open());
+        assert_eq!(StatefulSystem::<(), _>::snapshot(&system), (false, 1));
+
+        // The second failure within the window opens the breaker.
+        let seen = tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::Failure, TestMessage::Command(1)],
+        );
+        assert_eq!(seen, [TestMessage::Failure]);
+        assert!(system.is_open());
+        assert_eq!(messages.peek_next(), Some(&TestMessage::Rejected(1)));
+
+        // Still cooling down.
+        let seen = tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::Command(2)],
+        );
+        assert_eq!(seen, [TestMessage::Rejected(1)]);
+        assert_eq!(messages.peek_next(), Some(&TestMessage::Rejected(2)));
+
+        // Closed again after the cooldown.
+        let seen = tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::Command(3)],
+        );
+        assert_eq!(seen, [TestMessage::Rejected(2), TestMessage::Command(3)]);
+        assert!(!system.is_open());
+    }
+
+    #[test]
+    fn test_failures_outside_window_do_not_open() {
+        let mut system = breaker();
+        let mut messages = MessageQueue::new();
+
+        tick(&mut system, &mut (), &mut messages, &[TestMessage::Failure]);
+        tick(&mut system, &mut (), &mut messages, &[]);
+        tick(&mut system, &mut (), &mut messages, &[]);
+        tick(&mut system, &mut (), &mut messages, &[TestMessage::Failure]);
+        assert!(!system.is_open());
+    }
+}
@@ -0,0 +1,63 @@
+// src/systems/clock.rs
+
+// `ClockSystem` gives a program wall-clock awareness through the message queue. Every tick it
+// reads its `Clock` once and pushes the time message built from `now_ms` for the next tick,
+// so other systems can timestamp events without each holding and reading a clock.
+
+use crate::{clock::Clock, message_queue::MessageQueue, system::System};
+
+pub struct ClockSystem<Message, C> {
+    clock: C,
+    to_message: fn(u64) -> Message,
+}
+
+impl<Message, C> ClockSystem<Message, C>
+where
+    C: Clock,
+{
+    pub fn new(clock: C, to_message: fn(u64) -> Message) -> Self {
+        Self { clock, to_message }
+    }
+}
+
+impl<ProgramState, Message, C> System<ProgramState, Message> for ClockSystem<Message, C>
+where
+    C: Clock,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        messages.push((self.to_message)(self.clock.now_ms()));
+    }
+
+    fn push_hint(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Time(u64),
+    }
+
+    #[test]
+    fn test_time_follows_clock() {
+        let mut system = ClockSystem::new(ManualClock::stepping(1000, 20), TestMessage::Time);
+        let mut messages = MessageQueue::new();
+        let mut times = Vec::new();
+
+        for _ in 0..3 {
+            messages.next_tick();
+            times.extend(messages.iter().map(|TestMessage::Time(now_ms)| *now_ms));
+            system.update(&mut (), &mut messages);
+        }
+        messages.next_tick();
+        times.extend(messages.iter().map(|TestMessage::Time(now_ms)| *now_ms));
+
+        assert_eq!(times, [1000, 1020, 1040]);
+    }
+}
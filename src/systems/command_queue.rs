@@ -0,0 +1,78 @@
+// src/systems/command_queue.rs
+
+// `CommandQueueSystem` serializes command execution. Every command message is taken out of the
+// current tick and appended to an internal FIFO, and each tick the oldest held command is put
+// back at the front of the current tick. Systems scheduled after it therefore see at most one
+// command per tick, in arrival order, while other messages pass through untouched.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::collections::VecDeque;
+
+pub struct CommandQueueSystem<Message> {
+    is_command: fn(&Message) -> bool,
+    held: VecDeque<Message>,
+}
+
+impl<Message> CommandQueueSystem<Message> {
+    pub fn new(is_command: fn(&Message) -> bool) -> Self {
+        Self {
+            is_command,
+            held: VecDeque::new(),
+        }
+    }
+
+    pub fn held_count(&self) -> usize {
+        self.held.len()
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for CommandQueueSystem<Message> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let commands = messages.take_all_matching(self.is_command);
+        self.held.extend(commands);
+        if let Some(command) = self.held.pop_front() {
+            messages.current_tick_queue.push_front(command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Command(u32),
+        Status,
+    }
+
+    #[test]
+    fn test_commands_execute_one_per_tick() {
+        let mut system =
+            CommandQueueSystem::new(|message| matches!(message, TestMessage::Command(_)));
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Command(1));
+        messages.push(TestMessage::Status);
+        messages.push(TestMessage::Command(2));
+        messages.push(TestMessage::Command(3));
+
+        let mut executed = Vec::new();
+        for _ in 0..4 {
+            messages.next_tick();
+            system.update(&mut (), &mut messages);
+            executed.push(messages.iter().cloned().collect::<Vec<_>>());
+        }
+
+        assert_eq!(
+            executed,
+            [
+                Vec::from([TestMessage::Command(1), TestMessage::Status]),
+                Vec::from([TestMessage::Command(2)]),
+                Vec::from([TestMessage::Command(3)]),
+                Vec::new(),
+            ]
+        );
+        assert_eq!(system.held_count(), 0);
+    }
+}
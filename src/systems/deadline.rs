@@ -0,0 +1,133 @@
+// src/systems/deadline.rs
+
+// `DeadlineSystem` enforces per-command time limits. When it sees a message that starts a
+// command, it notes the command's key and the tick by which a completion must have arrived. A
+// completion message with the same key, seen within `deadline` ticks after the start, settles
+// the command. A command still pending when its deadline tick has been processed is dropped
+// and the timeout message built from its key is pushed for the next tick.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+pub struct DeadlineSystem<Message, Key> {
+    deadline: u64,
+    started: fn(&Message) -> Option<Key>,
+    completed: fn(&Message) -> Option<Key>,
+    timeout: fn(&Key) -> Message,
+    tick: u64,
+    pending: Vec<(Key, u64)>,
+}
+
+impl<Message, Key> DeadlineSystem<Message, Key>
+where
+    Key: PartialEq,
+{
+    pub fn new(
+        deadline: u64,
+        started: fn(&Message) -> Option<Key>,
+        completed: fn(&Message) -> Option<Key>,
+        timeout: fn(&Key) -> Message,
+    ) -> Self {
+        Self {
+            deadline,
+            started,
+            completed,
+            timeout,
+            tick: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl<ProgramState, Message, Key> System<ProgramState, Message> for DeadlineSystem<Message, Key>
+where
+    Key: PartialEq,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        let tick = self.tick;
+
+        for message in messages.iter() {
+            if let Some(key) = (self.completed)(message) {
+                self.pending.retain(|(pending_key, _)| *pending_key != key);
+            } else if let Some(key) = (self.started)(message) {
+                self.pending.push((key, tick + self.deadline));
+            }
+        }
+
+        let mut timed_out = Vec::new();
+        self.pending.retain(|(key, deadline_tick)| {
+            if *deadline_tick <= tick {
+                timed_out.push((self.timeout)(key));
+                false
+            } else {
+                true
+            }
+        });
+        for message in timed_out {
+            messages.push(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Start(u32),
+        Done(u32),
+        Timeout(u32),
+    }
+
+    fn started(message: &TestMessage) -> Option<u32> {
+        match message {
+            TestMessage::Start(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    fn completed(message: &TestMessage) -> Option<u32> {
+        match message {
+            TestMessage::Done(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_deadlines() {
+        let mut system = DeadlineSystem::new(3, started, completed, |id| TestMessage::Timeout(*id));
+        let mut messages = MessageQueue::new();
+        let mut timeouts = Vec::new();
+
+        for tick in 1..=6 {
+            match tick {
+                1 => {
+                    messages.push(TestMessage::Start(1));
+                    messages.push(TestMessage::Start(2));
+                }
+                // Command 1 completes on the last tick it is allowed to.
+                4 => messages.push(TestMessage::Done(1)),
+                // Command 2 completes too late.
+                6 => messages.push(TestMessage::Done(2)),
+                _ => (),
+            }
+            messages.next_tick();
+            timeouts.extend(
+                messages
+                    .iter()
+                    .filter(|message| matches!(message, TestMessage::Timeout(_)))
+                    .cloned(),
+            );
+            system.update(&mut (), &mut messages);
+        }
+
+        assert_eq!(timeouts, [TestMessage::Timeout(2)]);
+        assert_eq!(system.pending_count(), 0);
+    }
+}
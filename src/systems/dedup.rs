@@ -0,0 +1,88 @@
+// src/systems/dedup.rs
+
+// `DedupSystem` makes a message stream idempotent. It remembers every message it lets through
+// for a configurable number of ticks and removes equal messages from the current tick while
+// they are still remembered. Systems scheduled after it never see the repeated command.
+
+use crate::{message_queue::MessageQueue, system::System};
+use core::hash::Hash;
+use hashbrown::HashMap;
+
+pub struct DedupSystem<Message> {
+    window: u64,
+    tick: u64,
+    seen: HashMap<Message, u64>,
+}
+
+impl<Message> DedupSystem<Message>
+where
+    Message: Hash + Eq + Clone,
+{
+    // A message is dropped if an equal message was let through less than `window` ticks ago.
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            tick: 0,
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for DedupSystem<Message>
+where
+    Message: Hash + Eq + Clone,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        let tick = self.tick;
+        let window = self.window;
+
+        // Forget messages that have fallen out of the window.
+        self.seen.retain(|_, seen_tick| tick - *seen_tick < window);
+
+        let seen = &mut self.seen;
+        messages.current_tick_queue.retain(|message| {
+            if seen.contains_key(message) {
+                false
+            } else {
+                seen.insert(message.clone(), tick);
+                true
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::test_support::tick;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_duplicate_within_window_is_dropped() {
+        let mut system = DedupSystem::new(3);
+        let mut messages = MessageQueue::new();
+
+        tick(&mut system, &mut (), &mut messages, &[1, 2, 1]);
+        assert_eq!(messages.iter().copied().collect::<Vec<_>>(), [1, 2]);
+
+        tick(&mut system, &mut (), &mut messages, &[1, 3]);
+        assert_eq!(messages.iter().copied().collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn test_duplicate_outside_window_passes() {
+        let mut system = DedupSystem::new(3);
+        let mut messages = MessageQueue::new();
+
+        tick(&mut system, &mut (), &mut messages, &[7]);
+        assert_eq!(messages.iter().count(), 1);
+        tick(&mut system, &mut (), &mut messages, &[]);
+        tick(&mut system, &mut (), &mut messages, &[7]);
+        assert_eq!(messages.iter().count(), 0);
+
+        // Three ticks after it was first let through, the message is accepted again.
+        tick(&mut system, &mut (), &mut messages, &[7]);
+        assert_eq!(messages.iter().copied().collect::<Vec<_>>(), [7]);
+    }
+}
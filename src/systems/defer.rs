@@ -0,0 +1,103 @@
+// src/systems/defer.rs
+
+// `DeferSystem` models "wait until ready". While the readiness predicate over the program state
+// is false, every current-tick message it is responsible for is taken out of the queue and held
+// in an internal buffer. On the first tick the predicate holds, the held messages are put back
+// at the front of the current tick, oldest first, so systems scheduled after it see them
+// without an extra tick of delay.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::collections::VecDeque;
+
+pub struct DeferSystem<ProgramState, Message> {
+    matches: fn(&Message) -> bool,
+    ready: fn(&ProgramState) -> bool,
+    deferred: VecDeque<Message>,
+}
+
+impl<ProgramState, Message> DeferSystem<ProgramState, Message> {
+    pub fn new(matches: fn(&Message) -> bool, ready: fn(&ProgramState) -> bool) -> Self {
+        Self {
+            matches,
+            ready,
+            deferred: VecDeque::new(),
+        }
+    }
+
+    pub fn deferred_count(&self) -> usize {
+        self.deferred.len()
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for DeferSystem<ProgramState, Message> {
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        if (self.ready)(program_state) {
+            while let Some(message) = self.deferred.pop_back() {
+                messages.current_tick_queue.push_front(message);
+            }
+        } else {
+            let deferred = messages.take_all_matching(self.matches);
+            self.deferred.extend(deferred);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::test_support::tick;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Engage(i32),
+        Log,
+    }
+
+    struct TestProgramState {
+        armed: bool,
+    }
+
+    #[test]
+    fn test_deferred_until_ready() {
+        let mut system = DeferSystem::new(
+            |message| matches!(message, TestMessage::Engage(_)),
+            |program_state: &TestProgramState| program_state.armed,
+        );
+        let mut program_state = TestProgramState { armed: false };
+        let mut messages = MessageQueue::new();
+
+        let seen = tick(
+            &mut system,
+            &mut program_state,
+            &mut messages,
+            &[TestMessage::Engage(1), TestMessage::Log],
+        );
+        assert_eq!(seen, [TestMessage::Log]);
+        let seen = tick(
+            &mut system,
+            &mut program_state,
+            &mut messages,
+            &[TestMessage::Engage(2)],
+        );
+        assert!(seen.is_empty());
+        assert_eq!(system.deferred_count(), 2);
+
+        program_state.armed = true;
+        let seen = tick(
+            &mut system,
+            &mut program_state,
+            &mut messages,
+            &[TestMessage::Engage(3)],
+        );
+        assert_eq!(
+            seen,
+            [
+                TestMessage::Engage(1),
+                TestMessage::Engage(2),
+                TestMessage::Engage(3)
+            ]
+        );
+        assert_eq!(system.deferred_count(), 0);
+    }
+}
@@ -0,0 +1,108 @@
+// src/systems/dispatch.rs
+
+// `DispatchTable` routes messages to handlers by enum variant. Instead of every system scanning
+// the whole current tick, each message looks up the handlers registered for its variant, keyed
+// by `core::mem::Discriminant`, and only those run. Handlers receive the program state and the
+// message, and collect the messages they want to push, which are queued for the next tick in
+// the order they were produced. Messages of variants without a handler are ignored.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+use core::mem::{self, Discriminant};
+use hashbrown::HashMap;
+
+pub type Handler<ProgramState, Message> = fn(&mut ProgramState, &Message, &mut Vec<Message>);
+
+pub struct DispatchTable<ProgramState, Message> {
+    handlers: HashMap<Discriminant<Message>, Vec<Handler<ProgramState, Message>>>,
+}
+
+impl<ProgramState, Message> DispatchTable<ProgramState, Message> {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    // Registers `handler` for the variant of `discriminant`. Handlers of the same variant run
+    // in the order they were registered.
+    pub fn register(
+        &mut self,
+        discriminant: Discriminant<Message>,
+        handler: Handler<ProgramState, Message>,
+    ) -> &mut Self {
+        self.handlers.entry(discriminant).or_default().push(handler);
+        self
+    }
+}
+
+impl<ProgramState, Message> Default for DispatchTable<ProgramState, Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for DispatchTable<ProgramState, Message> {
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut outgoing = Vec::new();
+        for message in messages.iter() {
+            if let Some(handlers) = self.handlers.get(&mem::discriminant(message)) {
+                for handler in handlers {
+                    handler(program_state, message, &mut outgoing);
+                }
+            }
+        }
+        for message in outgoing {
+            messages.push(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Add(i32),
+        Multiply(i32),
+        Log(i32),
+    }
+
+    fn add(total: &mut i32, message: &TestMessage, outgoing: &mut Vec<TestMessage>) {
+        if let TestMessage::Add(value) = message {
+            *total += value;
+            outgoing.push(TestMessage::Log(*total));
+        }
+    }
+
+    fn multiply(total: &mut i32, message: &TestMessage, _outgoing: &mut Vec<TestMessage>) {
+        if let TestMessage::Multiply(value) = message {
+            *total *= value;
+        }
+    }
+
+    #[test]
+    fn test_handlers_fire_for_their_variant() {
+        let mut table = DispatchTable::new();
+        table
+            .register(mem::discriminant(&TestMessage::Add(0)), add)
+            .register(mem::discriminant(&TestMessage::Multiply(0)), multiply);
+        let mut total = 1;
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Add(2));
+        messages.push(TestMessage::Log(99));
+        messages.push(TestMessage::Multiply(4));
+        messages.push(TestMessage::Add(3));
+        messages.next_tick();
+
+        table.update(&mut total, &mut messages);
+
+        assert_eq!(total, 15);
+        messages.next_tick();
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [&TestMessage::Log(3), &TestMessage::Log(15)]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// src/systems/guard.rs
+
+// `GuardSystem` enforces preconditions. Each current-tick message is checked against the guard
+// together with the program state, and messages that fail are removed before downstream
+// systems see them. A violation is logged by pushing the message built by `on_violation` for
+// the next tick, so a logger can report it without the guard knowing how output works.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+pub struct GuardSystem<ProgramState, Message> {
+    guard: fn(&Message, &ProgramState) -> bool,
+    on_violation: fn(&Message) -> Message,
+}
+
+impl<ProgramState, Message> GuardSystem<ProgramState, Message> {
+    pub fn new(
+        guard: fn(&Message, &ProgramState) -> bool,
+        on_violation: fn(&Message) -> Message,
+    ) -> Self {
+        Self {
+            guard,
+            on_violation,
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for GuardSystem<ProgramState, Message> {
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut violations = Vec::new();
+        messages.current_tick_queue.retain(|message| {
+            if (self.guard)(message, program_state) {
+                true
+            } else {
+                violations.push((self.on_violation)(message));
+                false
+            }
+        });
+        for violation in violations {
+            messages.push(violation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{
+        format,
+        string::{String, ToString},
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Withdraw(u32),
+        Log(String),
+    }
+
+    fn guard(message: &TestMessage, balance: &u32) -> bool {
+        match message {
+            TestMessage::Withdraw(amount) => amount <= balance,
+            _ => true,
+        }
+    }
+
+    fn on_violation(message: &TestMessage) -> TestMessage {
+        TestMessage::Log(format!("Rejected {:?}", message))
+    }
+
+    #[test]
+    fn test_violating_message_is_removed() {
+        let mut system = GuardSystem::new(guard, on_violation);
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Withdraw(30));
+        messages.push(TestMessage::Withdraw(80));
+        messages.push(TestMessage::Log("kept".to_string()));
+        messages.next_tick();
+
+        system.update(&mut 50, &mut messages);
+
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [
+                &TestMessage::Withdraw(30),
+                &TestMessage::Log("kept".to_string())
+            ]
+        );
+        messages.next_tick();
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [&TestMessage::Log("Rejected Withdraw(80)".to_string())]
+        );
+    }
+}
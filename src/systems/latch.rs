@@ -0,0 +1,105 @@
+// src/systems/latch.rs
+
+// `LatchSystem` models sticky signals, like the calculator's accumulator but for any value. A
+// set message captures a value, and from then on the latch pushes the message built from that
+// value every tick, until a clear message releases it. When a tick contains several set or
+// clear messages, the last one wins.
+
+use crate::{message_queue::MessageQueue, system::System};
+
+pub struct LatchSystem<Message, Value> {
+    value: Option<Value>,
+    set_value: fn(&Message) -> Option<Value>,
+    is_clear: fn(&Message) -> bool,
+    emit: fn(&Value) -> Message,
+}
+
+impl<Message, Value> LatchSystem<Message, Value> {
+    pub fn new(
+        set_value: fn(&Message) -> Option<Value>,
+        is_clear: fn(&Message) -> bool,
+        emit: fn(&Value) -> Message,
+    ) -> Self {
+        Self {
+            value: None,
+            set_value,
+            is_clear,
+            emit,
+        }
+    }
+
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+}
+
+impl<ProgramState, Message, Value> System<ProgramState, Message> for LatchSystem<Message, Value> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        for message in messages.iter() {
+            if let Some(value) = (self.set_value)(message) {
+                self.value = Some(value);
+            } else if (self.is_clear)(message) {
+                self.value = None;
+            }
+        }
+        if let Some(value) = &self.value {
+            messages.push((self.emit)(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Set(i32),
+        Clear,
+        Signal(i32),
+    }
+
+    fn set_value(message: &TestMessage) -> Option<i32> {
+        match message {
+            TestMessage::Set(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_latched_value_persists_until_cleared() {
+        let mut system = LatchSystem::new(
+            set_value,
+            |message| matches!(message, TestMessage::Clear),
+            |value| TestMessage::Signal(*value),
+        );
+        let mut messages = MessageQueue::new();
+        let mut signals = Vec::new();
+
+        for tick in 0..6 {
+            match tick {
+                0 => messages.push(TestMessage::Set(7)),
+                4 => messages.push(TestMessage::Clear),
+                _ => (),
+            }
+            messages.next_tick();
+            signals.push(messages.iter().cloned().collect::<Vec<_>>());
+            system.update(&mut (), &mut messages);
+        }
+
+        // The latch re-emits its value from the tick after the set until the clear arrives.
+        assert_eq!(
+            signals,
+            [
+                Vec::from([TestMessage::Set(7)]),
+                Vec::from([TestMessage::Signal(7)]),
+                Vec::from([TestMessage::Signal(7)]),
+                Vec::from([TestMessage::Signal(7)]),
+                Vec::from([TestMessage::Signal(7), TestMessage::Clear]),
+                Vec::new(),
+            ]
+        );
+        assert_eq!(system.value(), None);
+    }
+}
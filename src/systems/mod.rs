@@ -0,0 +1,43 @@
+// src/systems/mod.rs
+
+// The `systems` module collects reusable `System` implementations that are generic over the
+// program state and message types. Each one captures a pattern that otherwise ends up being
+// re-implemented by hand in every program built on the Flight Brain framework.
+
+// - Generic Building Blocks: None of the systems here know anything about a particular
+//   application. Behaviour is configured through constructor arguments and plain function
+//   pointers or closures, so they can be dropped into any `run` loop next to user systems.
+
+// - Current-Tick Processing: Like any other system, these operate on the messages of the
+//   current tick and may push new messages for the next tick. Systems that filter messages
+//   should be scheduled before the systems that consume those messages.
+
+pub mod backpressure;
+pub mod circuit_breaker;
+pub mod clock;
+pub mod command_queue;
+pub mod deadline;
+pub mod dedup;
+pub mod defer;
+pub mod dispatch;
+pub mod guard;
+pub mod latch;
+pub mod mode;
+pub mod perf_counter;
+pub mod phase;
+pub mod pipeline;
+pub mod priority;
+pub mod pump;
+pub mod saga;
+pub mod scoped;
+pub mod shell;
+pub mod snapshot;
+pub mod state_diff;
+pub mod state_invariant;
+pub mod telemetry;
+#[cfg(test)]
+mod test_support;
+pub mod throttled_logging;
+pub mod tween;
+pub mod validation;
+pub mod windowed_aggregate;
@@ -0,0 +1,149 @@
+// src/systems/mode.rs
+
+// `ModeSystem` replaces ad-hoc mode flags (such as the calculator's `batch_mode`) with an
+// explicit state machine. It watches the current tick for mode-change requests and, on every
+// real transition, pushes an exit message for the old mode followed by an enter message for
+// the new one. Requests for the mode that is already active are ignored.
+
+use crate::{
+    message_queue::MessageQueue,
+    system::{StatefulSystem, System},
+};
+use alloc::vec::Vec;
+
+pub struct ModeSystem<Message, Mode> {
+    mode: Mode,
+    requested_mode: fn(&Message) -> Option<Mode>,
+    on_exit: fn(&Mode) -> Message,
+    on_enter: fn(&Mode) -> Message,
+}
+
+impl<Message, Mode> ModeSystem<Message, Mode>
+where
+    Mode: Clone + PartialEq,
+{
+    pub fn new(
+        initial_mode: Mode,
+        requested_mode: fn(&Message) -> Option<Mode>,
+        on_exit: fn(&Mode) -> Message,
+        on_enter: fn(&Mode) -> Message,
+    ) -> Self {
+        Self {
+            mode: initial_mode,
+            requested_mode,
+            on_exit,
+            on_enter,
+        }
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+}
+
+impl<ProgramState, Message, Mode> System<ProgramState, Message> for ModeSystem<Message, Mode>
+where
+    Mode: Clone + PartialEq,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut transitions = Vec::new();
+        for message in messages.iter() {
+            if let Some(mode) = (self.requested_mode)(message) {
+                if mode != self.mode {
+                    transitions.push((self.mode.clone(), mode.clone()));
+                    self.mode = mode;
+                }
+            }
+        }
+        for (old_mode, new_mode) in transitions {
+            messages.push((self.on_exit)(&old_mode));
+            messages.push((self.on_enter)(&new_mode));
+        }
+    }
+}
+
+impl<ProgramState, Message, Mode> StatefulSystem<ProgramState, Message>
+    for ModeSystem<Message, Mode>
+where
+    Mode: Clone + PartialEq,
+{
+    type Snapshot = Mode;
+
+    fn snapshot(&self) -> Mode {
+        self.mode.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::test_support::tick;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMode {
+        Interactive,
+        Batch,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        SetMode(TestMode),
+        Exit(TestMode),
+        Enter(TestMode),
+    }
+
+    fn mode_system() -> ModeSystem<TestMessage, TestMode> {
+        ModeSystem::new(
+            TestMode::Interactive,
+            |message| match message {
+                TestMessage::SetMode(mode) => Some(mode.clone()),
+                _ => None,
+            },
+            |mode| TestMessage::Exit(mode.clone()),
+            |mode| TestMessage::Enter(mode.clone()),
+        )
+    }
+
+    #[test]
+    fn test_mode_transitions() {
+        let mut system = mode_system();
+        let mut messages = MessageQueue::new();
+
+        messages.push(TestMessage::SetMode(TestMode::Batch));
+        tick(&mut system, &mut (), &mut messages, &[]);
+        assert_eq!(system.mode(), &TestMode::Batch);
+        assert_eq!(StatefulSystem::<(), _>::snapshot(&system), TestMode::Batch);
+
+        messages.push(TestMessage::SetMode(TestMode::Interactive));
+        tick(&mut system, &mut (), &mut messages, &[]);
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [
+                &TestMessage::Exit(TestMode::Interactive),
+                &TestMessage::Enter(TestMode::Batch),
+                &TestMessage::SetMode(TestMode::Interactive),
+            ]
+        );
+        assert_eq!(system.mode(), &TestMode::Interactive);
+
+        tick(&mut system, &mut (), &mut messages, &[]);
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [
+                &TestMessage::Exit(TestMode::Batch),
+                &TestMessage::Enter(TestMode::Interactive),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_mode_is_ignored() {
+        let mut system = mode_system();
+        let mut messages = MessageQueue::new();
+
+        messages.push(TestMessage::SetMode(TestMode::Interactive));
+        tick(&mut system, &mut (), &mut messages, &[]);
+        assert_eq!(messages.peek_next(), None);
+        assert_eq!(system.mode(), &TestMode::Interactive);
+    }
+}
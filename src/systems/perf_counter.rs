@@ -0,0 +1,82 @@
+// src/systems/perf_counter.rs
+
+// `PerfCounterSystem` gives built-in throughput visibility. Every tick it counts the messages
+// in the current tick, adding them to a running total and to a rolling window covering the
+// last `window` ticks. The total and the average number of messages per tick over the window
+// are available through accessors, so a telemetry system or the update closure can report them.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::collections::VecDeque;
+
+pub struct PerfCounterSystem {
+    window: usize,
+    total: u64,
+    recent: VecDeque<usize>,
+    recent_sum: usize,
+}
+
+impl PerfCounterSystem {
+    pub fn new(window: usize) -> Self {
+        assert!(
+            0 < window,
+            "Performance counter window must be at least one tick."
+        );
+        Self {
+            window,
+            total: 0,
+            recent: VecDeque::with_capacity(window),
+            recent_sum: 0,
+        }
+    }
+
+    // Messages seen since the system was created.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    // Average messages per tick over the ticks in the window, or zero before the first tick.
+    pub fn average(&self) -> f64 {
+        if self.recent.is_empty() {
+            0.0
+        } else {
+            self.recent_sum as f64 / self.recent.len() as f64
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for PerfCounterSystem {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let count = messages.len();
+        self.total += count as u64;
+        if self.recent.len() == self.window {
+            self.recent_sum -= self.recent.pop_front().unwrap_or(0);
+        }
+        self.recent.push_back(count);
+        self.recent_sum += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average() {
+        let mut system = PerfCounterSystem::new(3);
+        let mut messages = MessageQueue::new();
+        assert_eq!(system.average(), 0.0);
+
+        // One, two, three, then six messages per tick.
+        for count in [1, 2, 3, 6] {
+            for message in 0..count {
+                messages.push(message);
+            }
+            messages.next_tick();
+            System::<(), i32>::update(&mut system, &mut (), &mut messages);
+        }
+
+        assert_eq!(system.total(), 12);
+        // The window holds the last three ticks: 2, 3 and 6 messages.
+        assert_eq!(system.average(), 11.0 / 3.0);
+    }
+}
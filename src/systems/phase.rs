@@ -0,0 +1,263 @@
+// src/systems/phase.rs
+
+// `PhaseSystem` formalizes the read, compute, write phasing that the calculator example gets
+// implicitly from its Input, Calculator and Output systems. Sub-systems are registered under a
+// `Phase`, and one update runs all of them, phase by phase, within a single tick.
+
+// - Private Queue: The phases talk through a queue owned by the `PhaseSystem`. The first phase
+//   sees a copy of the current tick, and every later phase sees the messages pushed by the phase
+//   before it, so data flows from input to output without waiting for further ticks. Phases
+//   without systems are skipped rather than swallowing what the previous phase produced. The
+//   private queue reports the tick of the outer queue, and sub-systems receive the outer
+//   `TickContext` when the `PhaseSystem` is updated through `update_ctx`.
+
+// - Outer Queue: The current tick is left as it is for the systems scheduled after the
+//   `PhaseSystem`. The messages pushed by the last phase are queued for the next tick of the
+//   outer queue.
+
+use crate::{
+    message_queue::MessageQueue,
+    system::{System, TickContext},
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Read,
+    Compute,
+    Write,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Read, Phase::Compute, Phase::Write];
+}
+
+type PhasedSystem<ProgramState, Message> = (Phase, Box<dyn System<ProgramState, Message>>);
+
+pub struct PhaseSystem<ProgramState, Message> {
+    systems: Vec<PhasedSystem<ProgramState, Message>>,
+    queue: MessageQueue<Message>,
+}
+
+impl<ProgramState, Message> PhaseSystem<ProgramState, Message> {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            queue: MessageQueue::new(),
+        }
+    }
+
+    // Systems of the same phase run in the order they were added.
+    pub fn add(
+        &mut self,
+        phase: Phase,
+        system: Box<dyn System<ProgramState, Message>>,
+    ) -> &mut Self {
+        self.systems.push((phase, system));
+        self
+    }
+}
+
+impl<ProgramState, Message> Default for PhaseSystem<ProgramState, Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ProgramState, Message: Clone> PhaseSystem<ProgramState, Message> {
+    fn run_phases(
+        &mut self,
+        ctx: Option<&TickContext>,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        if self.systems.is_empty() {
+            return;
+        }
+
+        self.queue.tick = messages.tick();
+        self.queue.current_tick_queue.clear();
+        self.queue
+            .current_tick_queue
+            .extend(messages.iter().cloned());
+        for phase in Phase::ALL {
+            let mut ran = false;
+            for (_, system) in self
+                .systems
+                .iter_mut()
+                .filter(|(system_phase, _)| phase == *system_phase)
+            {
+                match ctx {
+                    Some(ctx) => system.update_ctx(ctx, program_state, &mut self.queue),
+                    None => system.update(program_state, &mut self.queue),
+                }
+                ran = true;
+            }
+            if ran {
+                // Hand the messages on to the next phase without advancing the tick.
+                mem::swap(
+                    &mut self.queue.current_tick_queue,
+                    &mut self.queue.next_tick_queue,
+                );
+                self.queue.next_tick_queue.clear();
+            }
+        }
+        messages.extend_next_from_current(&mut self.queue);
+    }
+}
+
+impl<ProgramState, Message: Clone> System<ProgramState, Message>
+    for PhaseSystem<ProgramState, Message>
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.run_phases(None, program_state, messages);
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        self.run_phases(Some(ctx), program_state, messages);
+    }
+
+    fn push_hint(&self) -> usize {
+        self.systems
+            .iter()
+            .map(|(_, system)| system.push_hint())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Line(i32),
+        Number(i32),
+        Result(i32),
+        Printed(i32),
+    }
+
+    // Records the order in which the phases ran.
+    type Trace = Vec<&'static str>;
+
+    struct ReadSystem;
+
+    impl System<Trace, TestMessage> for ReadSystem {
+        fn update(&mut self, trace: &mut Trace, messages: &mut MessageQueue<TestMessage>) {
+            trace.push("read");
+            while let Some(message) = messages.pop_front_current() {
+                if let TestMessage::Line(value) = message {
+                    messages.push(TestMessage::Number(value));
+                }
+            }
+        }
+    }
+
+    struct ComputeSystem;
+
+    impl System<Trace, TestMessage> for ComputeSystem {
+        fn update(&mut self, trace: &mut Trace, messages: &mut MessageQueue<TestMessage>) {
+            trace.push("compute");
+            let mut results = Vec::new();
+            for message in messages.iter() {
+                if let TestMessage::Number(value) = message {
+                    results.push(TestMessage::Result(value * 10));
+                }
+            }
+            for result in results {
+                messages.push(result);
+            }
+        }
+    }
+
+    struct WriteSystem;
+
+    impl System<Trace, TestMessage> for WriteSystem {
+        fn update(&mut self, trace: &mut Trace, messages: &mut MessageQueue<TestMessage>) {
+            trace.push("write");
+            let mut printed = Vec::new();
+            for message in messages.iter() {
+                if let TestMessage::Result(value) = message {
+                    printed.push(TestMessage::Printed(*value));
+                }
+            }
+            for message in printed {
+                messages.push(message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_phases_run_in_order_within_one_tick() {
+        let mut system = PhaseSystem::new();
+        // Registered out of order on purpose.
+        system
+            .add(Phase::Write, Box::new(WriteSystem))
+            .add(Phase::Read, Box::new(ReadSystem))
+            .add(Phase::Compute, Box::new(ComputeSystem));
+        let mut trace = Vec::new();
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Line(4));
+        messages.next_tick();
+
+        system.update(&mut trace, &mut messages);
+
+        assert_eq!(trace, ["read", "compute", "write"]);
+        // The read phase consumed its copy; later systems still see the current tick.
+        assert_eq!(
+            messages.iter().cloned().collect::<Vec<_>>(),
+            vec![TestMessage::Line(4)]
+        );
+        messages.next_tick();
+        assert_eq!(
+            messages.iter().cloned().collect::<Vec<_>>(),
+            vec![TestMessage::Printed(40)]
+        );
+    }
+
+    // Records the tick its queue reports and the tick handed in through the context.
+    struct TickSystem;
+
+    impl System<Vec<(u64, u64)>, TestMessage> for TickSystem {
+        fn update(
+            &mut self,
+            ticks: &mut Vec<(u64, u64)>,
+            messages: &mut MessageQueue<TestMessage>,
+        ) {
+            ticks.push((messages.tick(), 0));
+        }
+
+        fn update_ctx(
+            &mut self,
+            ctx: &TickContext,
+            ticks: &mut Vec<(u64, u64)>,
+            messages: &mut MessageQueue<TestMessage>,
+        ) {
+            ticks.push((messages.tick(), ctx.tick));
+        }
+    }
+
+    #[test]
+    fn test_sub_systems_see_the_outer_tick() {
+        let mut system = PhaseSystem::new();
+        system
+            .add(Phase::Read, Box::new(TickSystem))
+            .add(Phase::Compute, Box::new(TickSystem))
+            .add(Phase::Write, Box::new(TickSystem));
+        let mut ticks = Vec::new();
+        let mut messages = MessageQueue::with_tick(4);
+        messages.next_tick();
+
+        system.update(&mut ticks, &mut messages);
+        system.update_ctx(&TickContext { tick: 5 }, &mut ticks, &mut messages);
+
+        assert_eq!(ticks, [(5, 0), (5, 0), (5, 0), (5, 5), (5, 5), (5, 5)]);
+    }
+}
@@ -0,0 +1,90 @@
+// src/systems/pipeline.rs
+
+// `Pipeline` chains two systems so they can be scheduled as one. Within a single update the
+// first system runs before the second, so the second sees the current tick after the first has
+// filtered or annotated it. Longer chains are built by nesting pipelines, and because boxed
+// systems are systems too, a stage may be a `Box<dyn System>`.
+
+use crate::{
+    message_queue::MessageQueue,
+    system::{System, TickContext},
+};
+
+pub struct Pipeline<First, Second> {
+    first: First,
+    second: Second,
+}
+
+impl<First, Second> Pipeline<First, Second> {
+    pub fn new(first: First, second: Second) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<ProgramState, Message, First, Second> System<ProgramState, Message> for Pipeline<First, Second>
+where
+    First: System<ProgramState, Message>,
+    Second: System<ProgramState, Message>,
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.first.update(program_state, messages);
+        self.second.update(program_state, messages);
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        self.first.update_ctx(ctx, program_state, messages);
+        self.second.update_ctx(ctx, program_state, messages);
+    }
+
+    fn push_hint(&self) -> usize {
+        self.first.push_hint() + self.second.push_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+
+    struct AddSystem(i32);
+
+    impl System<i32, ()> for AddSystem {
+        fn update(&mut self, program_state: &mut i32, _messages: &mut MessageQueue<()>) {
+            *program_state += self.0;
+        }
+    }
+
+    struct DoubleSystem;
+
+    impl System<i32, ()> for DoubleSystem {
+        fn update(&mut self, program_state: &mut i32, _messages: &mut MessageQueue<()>) {
+            *program_state *= 2;
+        }
+    }
+
+    #[test]
+    fn test_pipeline_order() {
+        let mut program_state = 1;
+        let mut pipeline = Pipeline::new(AddSystem(2), DoubleSystem);
+        pipeline.update(&mut program_state, &mut MessageQueue::new());
+        assert_eq!(program_state, 6);
+    }
+
+    #[test]
+    fn test_pipeline_with_boxed_system() {
+        let boxed: Box<dyn System<i32, ()>> = Box::new(AddSystem(3));
+        let mut pipeline = Pipeline::new(DoubleSystem, boxed);
+        let mut program_state = 1;
+        pipeline.update(&mut program_state, &mut MessageQueue::new());
+        assert_eq!(program_state, 5);
+
+        let mut nested: Box<dyn System<i32, ()>> = Box::new(pipeline);
+        nested.update(&mut program_state, &mut MessageQueue::new());
+        assert_eq!(program_state, 13);
+    }
+}
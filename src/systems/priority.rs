@@ -0,0 +1,155 @@
+// src/systems/priority.rs
+
+// `PrioritySystem` applies one priority to everything a wrapped system sends. The wrapped
+// system pushes messages as usual, typically via `Prioritized::from`, and after its update the
+// wrapper re-queues each newly pushed message with `push_with_priority` at the configured
+// level. Priority policy is then decided where the system is scheduled instead of inside it.
+
+// The wrapper tells the wrapped system's messages apart by where they end up: everything
+// appended behind the messages queued before the update is re-queued. The wrapped system must
+// therefore queue its messages with the plain `push`. Messages it places elsewhere with
+// `push_front`, `insert_next` or `push_with_priority`, or next-tick messages it removes, make
+// the wrong messages change priority.
+
+use crate::{
+    message_queue::MessageQueue,
+    priority::Prioritized,
+    system::{MessageMask, System, TickContext},
+};
+use alloc::vec::Vec;
+
+pub struct PrioritySystem<S> {
+    inner: S,
+    priority: u8,
+}
+
+impl<S> PrioritySystem<S> {
+    pub fn new(inner: S, priority: u8) -> Self {
+        Self { inner, priority }
+    }
+
+    // Re-queues everything appended behind the first `queued` next-tick messages at this
+    // system's priority.
+    fn requeue<Message>(&self, messages: &mut MessageQueue<Prioritized<Message>>, queued: usize) {
+        let start = queued.min(messages.next_len());
+        let pushed: Vec<_> = messages.next_tick_queue.drain(start..).collect();
+        for prioritized in pushed {
+            messages.push_with_priority(prioritized.message, self.priority);
+        }
+    }
+}
+
+impl<ProgramState, Message, S> System<ProgramState, Prioritized<Message>> for PrioritySystem<S>
+where
+    S: System<ProgramState, Prioritized<Message>>,
+{
+    fn update(
+        &mut self,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Prioritized<Message>>,
+    ) {
+        let queued = messages.next_len();
+        self.inner.update(program_state, messages);
+        self.requeue(messages, queued);
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Prioritized<Message>>,
+    ) {
+        let queued = messages.next_len();
+        self.inner.update_ctx(ctx, program_state, messages);
+        self.requeue(messages, queued);
+    }
+
+    fn subscriptions(&self) -> MessageMask {
+        self.inner.subscriptions()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn push_hint(&self) -> usize {
+        self.inner.push_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SendSystem(&'static str);
+
+    impl System<(), Prioritized<&'static str>> for SendSystem {
+        fn update(
+            &mut self,
+            _program_state: &mut (),
+            messages: &mut MessageQueue<Prioritized<&'static str>>,
+        ) {
+            messages.push(Prioritized::from(self.0));
+        }
+    }
+
+    #[test]
+    fn test_wrapped_messages_carry_priority() {
+        let mut routine = PrioritySystem::new(SendSystem("routine"), 1);
+        let mut alarm = PrioritySystem::new(SendSystem("alarm"), 9);
+        let mut plain = SendSystem("plain");
+        let mut messages = MessageQueue::new();
+
+        routine.update(&mut (), &mut messages);
+        plain.update(&mut (), &mut messages);
+        alarm.update(&mut (), &mut messages);
+        messages.next_tick();
+
+        let mut iter = messages.iter();
+        assert_eq!(iter.next(), Some(&Prioritized::new("alarm", 9)));
+        assert_eq!(iter.next(), Some(&Prioritized::new("routine", 1)));
+        assert_eq!(iter.next(), Some(&Prioritized::new("plain", 0)));
+        assert_eq!(iter.next(), None);
+    }
+
+    // Sends its message only if no message is pending for the next tick yet, and records the
+    // tick it was updated in through the context.
+    struct PoliteSystem(&'static str, u64);
+
+    impl System<(), Prioritized<&'static str>> for PoliteSystem {
+        fn update(
+            &mut self,
+            _program_state: &mut (),
+            messages: &mut MessageQueue<Prioritized<&'static str>>,
+        ) {
+            if messages.peek_next().is_none() {
+                messages.push(Prioritized::from(self.0));
+            }
+        }
+
+        fn update_ctx(
+            &mut self,
+            ctx: &TickContext,
+            program_state: &mut (),
+            messages: &mut MessageQueue<Prioritized<&'static str>>,
+        ) {
+            self.1 = ctx.tick;
+            self.update(program_state, messages);
+        }
+    }
+
+    #[test]
+    fn test_wrapped_system_sees_queued_messages() {
+        let mut polite = PrioritySystem::new(PoliteSystem("polite", 0), 5);
+        let mut messages = MessageQueue::new();
+
+        polite.update(&mut (), &mut messages);
+        assert_eq!(messages.peek_next(), Some(&Prioritized::new("polite", 5)));
+
+        messages.next_tick();
+        messages.push(Prioritized::from("queued"));
+        polite.update_ctx(&TickContext { tick: 7 }, &mut (), &mut messages);
+        assert_eq!(polite.inner.1, 7);
+        assert_eq!(messages.next_len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+// src/systems/pump.rs
+
+// `PumpSystem` generalizes the calculator example's non-blocking stdin handling. The platform
+// specific reading lives behind the `ByteSource` trait, so the framework never touches libc.
+
+// - Reading: Every tick the pump reads from the source until it reports no more bytes, and
+//   appends what it got to an internal buffer. Sources must not block.
+// - Framing: The buffer is split into frames at the delimiter, a newline unless configured
+//   otherwise. Each complete frame, without its delimiter, is handed to the decoder, and the
+//   resulting messages are pushed for the next tick. A partial frame stays buffered until the
+//   rest of it arrives.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+const READ_CHUNK_SIZE: usize = 64;
+
+pub trait ByteSource {
+    // Copies available bytes into `buf` and returns how many were copied. Returns zero when no
+    // bytes are available right now.
+    fn read(&mut self, buf: &mut [u8]) -> usize;
+}
+
+pub struct PumpSystem<Message, Source> {
+    source: Source,
+    decode: fn(&[u8]) -> Option<Message>,
+    delimiter: u8,
+    pending: Vec<u8>,
+}
+
+impl<Message, Source> PumpSystem<Message, Source>
+where
+    Source: ByteSource,
+{
+    // Frames that `decode` maps to `None` are dropped.
+    pub fn new(source: Source, decode: fn(&[u8]) -> Option<Message>) -> Self {
+        Self {
+            source,
+            decode,
+            delimiter: b'\n',
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+impl<ProgramState, Message, Source> System<ProgramState, Message> for PumpSystem<Message, Source>
+where
+    Source: ByteSource,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        loop {
+            let bytes_read = self.source.read(&mut chunk);
+            if 0 == bytes_read {
+                break;
+            }
+            self.pending.extend_from_slice(&chunk[..bytes_read]);
+        }
+
+        let mut frame_start = 0;
+        while let Some(length) = self.pending[frame_start..]
+            .iter()
+            .position(|byte| *byte == self.delimiter)
+        {
+            if let Some(message) = (self.decode)(&self.pending[frame_start..frame_start + length]) {
+                messages.push(message);
+            }
+            frame_start += length + 1;
+        }
+        self.pending.drain(..frame_start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{
+        string::{String, ToString},
+        vec,
+    };
+
+    // Delivers one scripted chunk per tick; an empty chunk means nothing arrived.
+    struct MockByteSource {
+        chunks: Vec<&'static [u8]>,
+        ready: Option<&'static [u8]>,
+    }
+
+    impl MockByteSource {
+        fn next_tick(&mut self) {
+            self.ready = Some(self.chunks.remove(0));
+        }
+    }
+
+    impl ByteSource for MockByteSource {
+        fn read(&mut self, buf: &mut [u8]) -> usize {
+            match self.ready.take() {
+                Some(bytes) => {
+                    buf[..bytes.len()].copy_from_slice(bytes);
+                    bytes.len()
+                }
+                None => 0,
+            }
+        }
+    }
+
+    fn decode(frame: &[u8]) -> Option<String> {
+        core::str::from_utf8(frame).ok().map(ToString::to_string)
+    }
+
+    #[test]
+    fn test_partial_and_complete_lines() {
+        let mut system = PumpSystem::new(
+            MockByteSource {
+                chunks: vec![b"1 +", b"", b" 2\n3\n4 ", b"* 5\n"],
+                ready: None,
+            },
+            decode,
+        );
+        let mut messages = MessageQueue::new();
+        let mut lines = Vec::new();
+
+        for _ in 0..4 {
+            system.source.next_tick();
+            messages.next_tick();
+            system.update(&mut (), &mut messages);
+            lines.push(messages.drain_all());
+        }
+
+        assert_eq!(
+            lines,
+            [
+                Vec::<String>::new(),
+                Vec::new(),
+                vec!["1 + 2".to_string(), "3".to_string()],
+                vec!["4 * 5".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_delimiter() {
+        let mut system = PumpSystem::new(
+            MockByteSource {
+                chunks: vec![b"a;b;c"],
+                ready: None,
+            },
+            decode,
+        )
+        .with_delimiter(b';');
+        let mut messages = MessageQueue::new();
+
+        system.source.next_tick();
+        system.update(&mut (), &mut messages);
+
+        assert_eq!(messages.drain_all(), ["a", "b"]);
+    }
+}
@@ -0,0 +1,182 @@
+// src/systems/saga.rs
+
+// `SagaSystem` runs a multi-step workflow through the message queue. A saga is a list of
+// steps, each of which sends an action message and then waits, possibly for many ticks, for a
+// message reporting that the action completed or failed.
+
+// - Progress: When the start message arrives the first action is sent. Each completion sends
+//   the next step's action until the last step has completed.
+
+// - Compensation: When a step fails, the compensation messages of all previously completed
+//   steps are sent, most recent first, undoing the work done so far.
+
+// A finished or failed saga can be started again by another start message.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+pub struct SagaStep<Message> {
+    pub action: fn() -> Message,
+    pub is_complete: fn(&Message) -> bool,
+    pub is_failure: fn(&Message) -> bool,
+    pub compensate: fn() -> Message,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SagaState {
+    Idle,
+    // Waiting for the outcome of the step with this index.
+    Running(usize),
+    Completed,
+    // The step with this index failed and earlier steps were compensated.
+    Failed(usize),
+}
+
+pub struct SagaSystem<Message> {
+    is_start: fn(&Message) -> bool,
+    steps: Vec<SagaStep<Message>>,
+    state: SagaState,
+}
+
+impl<Message> SagaSystem<Message> {
+    pub fn new(is_start: fn(&Message) -> bool, steps: Vec<SagaStep<Message>>) -> Self {
+        Self {
+            is_start,
+            steps,
+            state: SagaState::Idle,
+        }
+    }
+
+    pub fn state(&self) -> SagaState {
+        self.state
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for SagaSystem<Message> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut outgoing = Vec::new();
+        for message in messages.iter() {
+            match self.state {
+                SagaState::Running(index) => {
+                    let step = &self.steps[index];
+                    if (step.is_failure)(message) {
+                        for completed in self.steps[..index].iter().rev() {
+                            outgoing.push((completed.compensate)());
+                        }
+                        self.state = SagaState::Failed(index);
+                    } else if (step.is_complete)(message) {
+                        if index + 1 < self.steps.len() {
+                            outgoing.push((self.steps[index + 1].action)());
+                            self.state = SagaState::Running(index + 1);
+                        } else {
+                            self.state = SagaState::Completed;
+                        }
+                    }
+                }
+                _ => {
+                    if (self.is_start)(message) && !self.steps.is_empty() {
+                        outgoing.push((self.steps[0].action)());
+                        self.state = SagaState::Running(0);
+                    }
+                }
+            }
+        }
+        for message in outgoing {
+            messages.push(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::test_support::tick;
+    use alloc::vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Start,
+        Reserve,
+        Reserved,
+        Release,
+        Charge,
+        Charged,
+        ChargeFailed,
+        Refund,
+    }
+
+    fn saga() -> SagaSystem<TestMessage> {
+        SagaSystem::new(
+            |message| TestMessage::Start == *message,
+            vec![
+                SagaStep {
+                    action: || TestMessage::Reserve,
+                    is_complete: |message| TestMessage::Reserved == *message,
+                    is_failure: |_| false,
+                    compensate: || TestMessage::Release,
+                },
+                SagaStep {
+                    action: || TestMessage::Charge,
+                    is_complete: |message| TestMessage::Charged == *message,
+                    is_failure: |message| TestMessage::ChargeFailed == *message,
+                    compensate: || TestMessage::Refund,
+                },
+            ],
+        )
+    }
+
+    // The messages the saga pushed during the last tick.
+    fn pushed(messages: &MessageQueue<TestMessage>) -> Vec<TestMessage> {
+        messages.iter_next().cloned().collect()
+    }
+
+    #[test]
+    fn test_saga_completes() {
+        let mut system = saga();
+        let mut messages = MessageQueue::new();
+
+        tick(&mut system, &mut (), &mut messages, &[TestMessage::Start]);
+        assert_eq!(pushed(&messages), [TestMessage::Reserve]);
+        assert_eq!(system.state(), SagaState::Running(0));
+
+        // Nothing happens until the step reports back.
+        tick(&mut system, &mut (), &mut messages, &[]);
+        assert!(pushed(&messages).is_empty());
+
+        tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::Reserved],
+        );
+        assert_eq!(pushed(&messages), [TestMessage::Charge]);
+        assert_eq!(system.state(), SagaState::Running(1));
+
+        tick(&mut system, &mut (), &mut messages, &[TestMessage::Charged]);
+        assert!(pushed(&messages).is_empty());
+        assert_eq!(system.state(), SagaState::Completed);
+    }
+
+    #[test]
+    fn test_saga_failure_compensates() {
+        let mut system = saga();
+        let mut messages = MessageQueue::new();
+
+        tick(&mut system, &mut (), &mut messages, &[TestMessage::Start]);
+        tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::Reserved],
+        );
+
+        tick(
+            &mut system,
+            &mut (),
+            &mut messages,
+            &[TestMessage::ChargeFailed],
+        );
+        assert_eq!(pushed(&messages), [TestMessage::Release]);
+        assert_eq!(system.state(), SagaState::Failed(1));
+    }
+}
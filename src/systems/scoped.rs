@@ -0,0 +1,95 @@
+// src/systems/scoped.rs
+
+// `ScopedSystem` bounds another system in time. It forwards updates to the inner system only
+// during the ticks `start..end`, as counted by the message queue, and does nothing outside
+// that range. This suits one-shot startup sequences and monitoring windows of fixed length.
+
+use crate::{
+    message_queue::MessageQueue,
+    system::{MessageMask, System, TickContext},
+};
+use core::ops::Range;
+
+pub struct ScopedSystem<S> {
+    inner: S,
+    ticks: Range<u64>,
+}
+
+impl<S> ScopedSystem<S> {
+    pub fn new(inner: S, start: u64, end: u64) -> Self {
+        Self {
+            inner,
+            ticks: start..end,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<ProgramState, Message, S> System<ProgramState, Message> for ScopedSystem<S>
+where
+    S: System<ProgramState, Message>,
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        if self.ticks.contains(&messages.tick()) {
+            self.inner.update(program_state, messages);
+        }
+    }
+
+    fn update_ctx(
+        &mut self,
+        ctx: &TickContext,
+        program_state: &mut ProgramState,
+        messages: &mut MessageQueue<Message>,
+    ) {
+        if self.ticks.contains(&messages.tick()) {
+            self.inner.update_ctx(ctx, program_state, messages);
+        }
+    }
+
+    fn subscriptions(&self) -> MessageMask {
+        self.inner.subscriptions()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn push_hint(&self) -> usize {
+        if self.ticks.is_empty() {
+            0
+        } else {
+            self.inner.push_hint()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    struct RecordingSystem;
+
+    impl System<Vec<u64>, ()> for RecordingSystem {
+        fn update(&mut self, ticks: &mut Vec<u64>, messages: &mut MessageQueue<()>) {
+            ticks.push(messages.tick());
+        }
+    }
+
+    #[test]
+    fn test_inner_runs_only_within_range() {
+        let mut system = ScopedSystem::new(RecordingSystem, 3, 6);
+        let mut ticks = Vec::new();
+        let mut messages = MessageQueue::new();
+
+        for _ in 0..8 {
+            messages.next_tick();
+            system.update(&mut ticks, &mut messages);
+        }
+
+        assert_eq!(ticks, [3, 4, 5]);
+    }
+}
@@ -0,0 +1,212 @@
+// src/systems/shell.rs
+
+// `ShellSystem` captures the read-eval-print loop used by the calculator example. The cycle
+// looks like this:
+
+// - Poll: When the message queue has gone idle, one line is read from the `LineSource`.
+// - Parse: The line is turned into messages by the user supplied parser and pushed for the
+//   next tick, where the domain systems pick them up.
+// - Collect: While the domain systems are busy, every message the formatter recognizes is
+//   rendered into an output line and buffered.
+// - Flush: Once a tick passes without any messages, the buffered lines are written to the
+//   `LineSink` and the shell polls for the next line.
+
+// When the source runs dry the shell pushes the end-of-input message and stops polling. A new
+// program only needs to supply the parser, the formatter, and the systems doing the real work.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::{string::String, vec::Vec};
+
+pub trait LineSource {
+    // Returns the next line of input without the line terminator, or `None` at end of input.
+    fn read_line(&mut self) -> Option<String>;
+}
+
+pub trait LineSink {
+    fn write_line(&mut self, line: &str);
+}
+
+pub struct ShellSystem<Message, Input, Output> {
+    input: Input,
+    output: Output,
+    parse: fn(&str) -> Vec<Message>,
+    format: fn(&Message) -> Option<String>,
+    end_of_input: fn() -> Message,
+    pending_output: Vec<String>,
+    done: bool,
+}
+
+impl<Message, Input, Output> ShellSystem<Message, Input, Output>
+where
+    Input: LineSource,
+    Output: LineSink,
+{
+    pub fn new(
+        input: Input,
+        output: Output,
+        parse: fn(&str) -> Vec<Message>,
+        format: fn(&Message) -> Option<String>,
+        end_of_input: fn() -> Message,
+    ) -> Self {
+        Self {
+            input,
+            output,
+            parse,
+            format,
+            end_of_input,
+            pending_output: Vec::new(),
+            done: false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn flush_output(&mut self) {
+        for line in self.pending_output.drain(..) {
+            self.output.write_line(&line);
+        }
+    }
+
+    fn poll_input(&mut self, messages: &mut MessageQueue<Message>) {
+        match self.input.read_line() {
+            Some(line) => {
+                for message in (self.parse)(&line) {
+                    messages.push(message);
+                }
+            }
+            None => {
+                self.done = true;
+                messages.push((self.end_of_input)());
+            }
+        }
+    }
+}
+
+impl<ProgramState, Message, Input, Output> System<ProgramState, Message>
+    for ShellSystem<Message, Input, Output>
+where
+    Input: LineSource,
+    Output: LineSink,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        let mut idle = true;
+        for message in messages.iter() {
+            idle = false;
+            if let Some(line) = (self.format)(message) {
+                self.pending_output.push(line);
+            }
+        }
+
+        if idle {
+            self.flush_output();
+            if !self.done {
+                self.poll_input(messages);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::ToString, vec};
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Number(i32),
+        Result(i32),
+        Error(String),
+        Shutdown,
+    }
+
+    struct ScriptedInput {
+        lines: Vec<&'static str>,
+    }
+
+    impl LineSource for ScriptedInput {
+        fn read_line(&mut self) -> Option<String> {
+            if self.lines.is_empty() {
+                None
+            } else {
+                Some(self.lines.remove(0).to_string())
+            }
+        }
+    }
+
+    struct RecordingOutput {
+        lines: Vec<String>,
+    }
+
+    impl LineSink for RecordingOutput {
+        fn write_line(&mut self, line: &str) {
+            self.lines.push(line.to_string());
+        }
+    }
+
+    fn parse(line: &str) -> Vec<TestMessage> {
+        match line.parse::<i32>() {
+            Ok(value) => vec![TestMessage::Number(value)],
+            Err(_) => vec![TestMessage::Error(format!("Not a number: {}", line))],
+        }
+    }
+
+    fn format_message(message: &TestMessage) -> Option<String> {
+        match message {
+            TestMessage::Result(value) => Some(format!("= {}", value)),
+            TestMessage::Error(text) => Some(format!("Error: {}", text)),
+            _ => None,
+        }
+    }
+
+    // Domain logic: doubles every number it sees.
+    struct DoublingSystem;
+
+    impl System<bool, TestMessage> for DoublingSystem {
+        fn update(&mut self, done: &mut bool, messages: &mut MessageQueue<TestMessage>) {
+            let mut results = Vec::new();
+            for message in messages.iter() {
+                match message {
+                    TestMessage::Number(value) => results.push(TestMessage::Result(value * 2)),
+                    TestMessage::Shutdown => *done = true,
+                    _ => (),
+                }
+            }
+            for result in results {
+                messages.push(result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scripted_shell() {
+        let mut done = false;
+        let mut messages = MessageQueue::new();
+        let mut doubling = DoublingSystem;
+        let mut shell = ShellSystem::new(
+            ScriptedInput {
+                lines: vec!["2", "x", "21"],
+            },
+            RecordingOutput { lines: Vec::new() },
+            parse,
+            format_message,
+            || TestMessage::Shutdown,
+        );
+
+        let mut ticks = 0;
+        while !done {
+            ticks += 1;
+            assert!(ticks < 32, "Shell did not reach end of input.");
+            messages.next_tick();
+            doubling.update(&mut done, &mut messages);
+            shell.update(&mut done, &mut messages);
+        }
+
+        assert!(shell.is_done());
+        assert_eq!(
+            shell.output.lines,
+            vec!["= 4", "Error: Not a number: x", "= 42"]
+        );
+    }
+}
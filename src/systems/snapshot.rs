@@ -0,0 +1,57 @@
+// src/systems/snapshot.rs
+
+// `SnapshotSystem` publishes a view of the program state to code outside the loop, such as a
+// display or telemetry link. Every tick it projects the state into a value and stores it in a
+// shared `RefCell`. The reader keeps its own handle to the cell and reads the latest snapshot
+// whenever it likes, without access to the program state or the message queue.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+pub struct SnapshotSystem<ProgramState, Value> {
+    project: fn(&ProgramState) -> Value,
+    shared: Rc<RefCell<Value>>,
+}
+
+impl<ProgramState, Value> SnapshotSystem<ProgramState, Value> {
+    // `shared` holds the initial value until the first tick replaces it.
+    pub fn new(project: fn(&ProgramState) -> Value, shared: Rc<RefCell<Value>>) -> Self {
+        Self { project, shared }
+    }
+}
+
+impl<ProgramState, Message, Value> System<ProgramState, Message>
+    for SnapshotSystem<ProgramState, Value>
+{
+    fn update(&mut self, program_state: &mut ProgramState, _messages: &mut MessageQueue<Message>) {
+        *self.shared.borrow_mut() = (self.project)(program_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestProgramState {
+        altitude: i32,
+    }
+
+    #[test]
+    fn test_snapshot_matches_state() {
+        let shared = Rc::new(RefCell::new(0));
+        let mut system = SnapshotSystem::new(
+            |program_state: &TestProgramState| program_state.altitude,
+            shared.clone(),
+        );
+        let mut program_state = TestProgramState { altitude: 1200 };
+        let mut messages: MessageQueue<()> = MessageQueue::new();
+
+        system.update(&mut program_state, &mut messages);
+        assert_eq!(*shared.borrow(), 1200);
+
+        program_state.altitude = 1500;
+        system.update(&mut program_state, &mut messages);
+        assert_eq!(*shared.borrow(), 1500);
+    }
+}
@@ -0,0 +1,76 @@
+// src/systems/state_diff.rs
+
+// `StateDiffSystem` turns state changes into messages, so display systems can react to actual
+// changes instead of redrawing every tick. It keeps a copy of the program state from its
+// previous update and, whenever the state differs from that copy, pushes the message built by
+// `on_change` from the old and the new state. The first update only records the baseline.
+
+use crate::{message_queue::MessageQueue, system::System};
+
+pub struct StateDiffSystem<ProgramState, Message> {
+    previous: Option<ProgramState>,
+    on_change: fn(&ProgramState, &ProgramState) -> Message,
+}
+
+impl<ProgramState, Message> StateDiffSystem<ProgramState, Message>
+where
+    ProgramState: Clone + PartialEq,
+{
+    pub fn new(on_change: fn(&ProgramState, &ProgramState) -> Message) -> Self {
+        Self {
+            previous: None,
+            on_change,
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for StateDiffSystem<ProgramState, Message>
+where
+    ProgramState: Clone + PartialEq,
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        match &mut self.previous {
+            Some(previous) if *previous != *program_state => {
+                messages.push((self.on_change)(previous, program_state));
+                previous.clone_from(program_state);
+            }
+            Some(_) => (),
+            None => self.previous = Some(program_state.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Changed(i32, i32),
+    }
+
+    #[test]
+    fn test_change_message_only_on_change() {
+        let mut system =
+            StateDiffSystem::new(|old: &i32, new: &i32| TestMessage::Changed(*old, *new));
+        let mut messages = MessageQueue::new();
+        let mut changes = Vec::new();
+
+        for value in [1, 1, 2, 2, 2, 5] {
+            let mut program_state = value;
+            messages.next_tick();
+            system.update(&mut program_state, &mut messages);
+            changes.push(
+                messages
+                    .peek_next()
+                    .map(|TestMessage::Changed(old, new)| (*old, *new)),
+            );
+        }
+
+        assert_eq!(
+            changes,
+            [None, None, Some((1, 2)), None, None, Some((2, 5))]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+// src/systems/state_invariant.rs
+
+// `StateInvariantSystem` centralizes self-healing checks on the program state. Every tick each
+// registered invariant is checked. A violation pushes the error message built from the
+// check's explanation for the next tick and, if the invariant has one, applies its repair so
+// that the systems running after it see a valid state again.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::{string::String, vec::Vec};
+
+struct Invariant<ProgramState> {
+    check: fn(&ProgramState) -> Result<(), String>,
+    repair: Option<fn(&mut ProgramState)>,
+}
+
+pub struct StateInvariantSystem<ProgramState, Message> {
+    invariants: Vec<Invariant<ProgramState>>,
+    to_error: fn(String) -> Message,
+}
+
+impl<ProgramState, Message> StateInvariantSystem<ProgramState, Message> {
+    pub fn new(to_error: fn(String) -> Message) -> Self {
+        Self {
+            invariants: Vec::new(),
+            to_error,
+        }
+    }
+
+    // Invariants are checked in the order they were added.
+    pub fn invariant(
+        &mut self,
+        check: fn(&ProgramState) -> Result<(), String>,
+        repair: Option<fn(&mut ProgramState)>,
+    ) -> &mut Self {
+        self.invariants.push(Invariant { check, repair });
+        self
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message>
+    for StateInvariantSystem<ProgramState, Message>
+{
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        for invariant in self.invariants.iter() {
+            if let Err(error) = (invariant.check)(program_state) {
+                messages.push((self.to_error)(error));
+                if let Some(repair) = invariant.repair {
+                    repair(program_state);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::ToString};
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Error(String),
+    }
+
+    struct TestProgramState {
+        throttle: i32,
+    }
+
+    fn throttle_in_range(program_state: &TestProgramState) -> Result<(), String> {
+        if (0..=100).contains(&program_state.throttle) {
+            Ok(())
+        } else {
+            Err(format!("Throttle out of range: {}", program_state.throttle))
+        }
+    }
+
+    #[test]
+    fn test_violation_is_reported_and_repaired() {
+        let mut system = StateInvariantSystem::new(TestMessage::Error);
+        system.invariant(
+            throttle_in_range,
+            Some(|program_state| program_state.throttle = program_state.throttle.clamp(0, 100)),
+        );
+        let mut program_state = TestProgramState { throttle: 50 };
+        let mut messages = MessageQueue::new();
+
+        system.update(&mut program_state, &mut messages);
+        assert_eq!(messages.peek_next(), None);
+
+        program_state.throttle = 130;
+        system.update(&mut program_state, &mut messages);
+        assert_eq!(program_state.throttle, 100);
+        assert!(throttle_in_range(&program_state).is_ok());
+        messages.next_tick();
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [&TestMessage::Error(
+                "Throttle out of range: 130".to_string()
+            )]
+        );
+    }
+}
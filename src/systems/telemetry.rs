@@ -0,0 +1,76 @@
+// src/systems/telemetry.rs
+
+// `TelemetrySystem` periodically reports on the program state. Every `period` ticks it hands
+// the state to a user supplied summary function and pushes the result as a message for the
+// next tick, where an output or logging system can forward it to a dashboard.
+
+use crate::{message_queue::MessageQueue, system::System};
+
+pub struct TelemetrySystem<ProgramState, Message> {
+    period: u64,
+    tick: u64,
+    summarize: fn(&ProgramState) -> Message,
+}
+
+impl<ProgramState, Message> TelemetrySystem<ProgramState, Message> {
+    pub fn new(period: u64, summarize: fn(&ProgramState) -> Message) -> Self {
+        assert!(0 < period, "Telemetry period must be at least one tick.");
+        Self {
+            period,
+            tick: 0,
+            summarize,
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message>
+    for TelemetrySystem<ProgramState, Message>
+{
+    // `u64::is_multiple_of` needs Rust 1.87, so the remainder is compared by hand.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn update(&mut self, program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        if 0 == self.tick % self.period {
+            messages.push((self.summarize)(program_state));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestProgramState {
+        altitude: i32,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Telemetry(i32),
+    }
+
+    fn summarize(program_state: &TestProgramState) -> TestMessage {
+        TestMessage::Telemetry(program_state.altitude)
+    }
+
+    #[test]
+    fn test_telemetry_every_fifth_tick() {
+        let mut program_state = TestProgramState { altitude: 0 };
+        let mut messages = MessageQueue::new();
+        let mut system = TelemetrySystem::new(5, summarize);
+
+        for tick in 1..=5 {
+            program_state.altitude = tick * 100;
+            messages.next_tick();
+            system.update(&mut program_state, &mut messages);
+            if tick < 5 {
+                assert_eq!(messages.peek_next(), None);
+            }
+        }
+
+        messages.next_tick();
+        let mut iter = messages.iter();
+        assert_eq!(iter.next(), Some(&TestMessage::Telemetry(500)));
+        assert_eq!(iter.next(), None);
+    }
+}
@@ -0,0 +1,22 @@
+// src/systems/test_support.rs
+
+// Helpers shared by the tests of the systems in this module.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::vec::Vec;
+
+// Pushes `pushed`, advances the queue to the next tick and updates `system` once. Returns the
+// current-tick messages as the system left them.
+pub(crate) fn tick<ProgramState, Message: Clone>(
+    system: &mut dyn System<ProgramState, Message>,
+    program_state: &mut ProgramState,
+    messages: &mut MessageQueue<Message>,
+    pushed: &[Message],
+) -> Vec<Message> {
+    for message in pushed {
+        messages.push(message.clone());
+    }
+    messages.next_tick();
+    system.update(program_state, messages);
+    messages.iter().cloned().collect()
+}
@@ -0,0 +1,130 @@
+// src/systems/throttled_logging.rs
+
+// `ThrottledLoggingSystem` keeps tight loops from flooding the log. The first log message with
+// a given text passes and opens a window of `window` ticks for that text. Identical log
+// messages arriving while the window is open are removed from the current tick and counted.
+// When the window closes, the summary message built from the text and the count, e.g.
+// "(repeated N times)", is pushed for the next tick if anything was suppressed, and the next
+// identical message passes again.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::{string::String, vec::Vec};
+use hashbrown::HashMap;
+
+pub struct ThrottledLoggingSystem<Message> {
+    window: u64,
+    log_text: fn(&Message) -> Option<&str>,
+    summary: fn(&str, u32) -> Message,
+    tick: u64,
+    // Window start tick and number of suppressed repeats, by log text.
+    open: HashMap<String, (u64, u32)>,
+}
+
+impl<Message> ThrottledLoggingSystem<Message> {
+    pub fn new(
+        window: u64,
+        log_text: fn(&Message) -> Option<&str>,
+        summary: fn(&str, u32) -> Message,
+    ) -> Self {
+        Self {
+            window,
+            log_text,
+            summary,
+            tick: 0,
+            open: HashMap::new(),
+        }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for ThrottledLoggingSystem<Message> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        self.tick += 1;
+        let tick = self.tick;
+        let window = self.window;
+
+        let mut summaries = Vec::new();
+        self.open.retain(|text, (start_tick, suppressed)| {
+            if *start_tick + window <= tick {
+                if 0 < *suppressed {
+                    summaries.push((self.summary)(text, *suppressed));
+                }
+                false
+            } else {
+                true
+            }
+        });
+
+        let log_text = self.log_text;
+        let open = &mut self.open;
+        messages
+            .current_tick_queue
+            .retain(|message| match log_text(message) {
+                Some(text) => match open.get_mut(text) {
+                    Some((_, suppressed)) => {
+                        *suppressed += 1;
+                        false
+                    }
+                    None => {
+                        open.insert(String::from(text), (tick, 0));
+                        true
+                    }
+                },
+                None => true,
+            });
+
+        for summary in summaries {
+            messages.push(summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::ToString};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Log(String),
+        Value(i32),
+    }
+
+    fn log_text(message: &TestMessage) -> Option<&str> {
+        match message {
+            TestMessage::Log(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn summary(text: &str, repeats: u32) -> TestMessage {
+        TestMessage::Log(format!("{} (repeated {} times)", text, repeats))
+    }
+
+    #[test]
+    fn test_repeated_log_is_summarized() {
+        let mut system = ThrottledLoggingSystem::new(3, log_text, summary);
+        let mut messages = MessageQueue::new();
+        let mut seen = Vec::new();
+
+        for tick in 1..=5 {
+            if tick <= 3 {
+                messages.push(TestMessage::Log("Overflow".to_string()));
+                messages.push(TestMessage::Value(tick));
+            }
+            messages.next_tick();
+            system.update(&mut (), &mut messages);
+            seen.extend(messages.iter().cloned());
+        }
+
+        assert_eq!(
+            seen,
+            [
+                TestMessage::Log("Overflow".to_string()),
+                TestMessage::Value(1),
+                TestMessage::Value(2),
+                TestMessage::Value(3),
+                TestMessage::Log("Overflow (repeated 2 times)".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+// src/systems/tween.rs
+
+// `TweenSystem` evolves a continuous value over ticks, e.g. to smooth a displayed reading.
+// When a message sets a new target, the value travels in a straight line from wherever it is
+// to the target over `duration` ticks. Each of those ticks the system pushes the message built
+// from the intermediate value, ending exactly on the target. A new target mid-way starts a new
+// tween from the current value.
+
+use crate::{message_queue::MessageQueue, system::System};
+
+// Values a `TweenSystem` can interpolate. `t` runs from 0.0 (`self`) to 1.0 (`target`).
+pub trait Lerp {
+    fn lerp(&self, target: &Self, t: f64) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, target: &Self, t: f64) -> Self {
+        self + (target - self) * t as f32
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, target: &Self, t: f64) -> Self {
+        self + (target - self) * t
+    }
+}
+
+pub struct TweenSystem<Message, Value> {
+    duration: u32,
+    target: fn(&Message) -> Option<Value>,
+    emit: fn(&Value) -> Message,
+    value: Value,
+    from: Value,
+    to: Value,
+    step: u32,
+}
+
+impl<Message, Value> TweenSystem<Message, Value>
+where
+    Value: Clone + Lerp,
+{
+    pub fn new(
+        initial: Value,
+        duration: u32,
+        target: fn(&Message) -> Option<Value>,
+        emit: fn(&Value) -> Message,
+    ) -> Self {
+        assert!(0 < duration, "Tween duration must be at least one tick.");
+        Self {
+            duration,
+            target,
+            emit,
+            value: initial.clone(),
+            from: initial.clone(),
+            to: initial,
+            step: duration,
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+impl<ProgramState, Message, Value> System<ProgramState, Message> for TweenSystem<Message, Value>
+where
+    Value: Clone + Lerp,
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        if let Some(target) = messages.iter().filter_map(self.target).last() {
+            self.from = self.value.clone();
+            self.to = target;
+            self.step = 0;
+        }
+        if self.step < self.duration {
+            self.step += 1;
+            self.value = self
+                .from
+                .lerp(&self.to, self.step as f64 / self.duration as f64);
+            messages.push((self.emit)(&self.value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Target(f64),
+        Display(f64),
+    }
+
+    fn target(message: &TestMessage) -> Option<f64> {
+        match message {
+            TestMessage::Target(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_linear_interpolation() {
+        let mut system = TweenSystem::new(0.0, 4, target, |value| TestMessage::Display(*value));
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Target(100.0));
+
+        let mut displayed = Vec::new();
+        for _ in 0..6 {
+            messages.next_tick();
+            system.update(&mut (), &mut messages);
+            displayed.extend(messages.peek_next().cloned());
+        }
+
+        assert_eq!(
+            displayed,
+            [
+                TestMessage::Display(25.0),
+                TestMessage::Display(50.0),
+                TestMessage::Display(75.0),
+                TestMessage::Display(100.0),
+            ]
+        );
+        assert_eq!(*system.value(), 100.0);
+    }
+}
@@ -0,0 +1,73 @@
+// src/systems/validation.rs
+
+// `ValidationSystem` sanitizes input before the systems that act on it. Every current-tick
+// message is passed to the validator, and any message that fails is replaced in place by the
+// error message built from the validator's explanation. Downstream systems therefore see
+// either a valid message or an error at the same position, never the invalid original.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::string::String;
+
+pub struct ValidationSystem<Message> {
+    validate: fn(&Message) -> Result<(), String>,
+    to_error: fn(String) -> Message,
+}
+
+impl<Message> ValidationSystem<Message> {
+    pub fn new(
+        validate: fn(&Message) -> Result<(), String>,
+        to_error: fn(String) -> Message,
+    ) -> Self {
+        Self { validate, to_error }
+    }
+}
+
+impl<ProgramState, Message> System<ProgramState, Message> for ValidationSystem<Message> {
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        messages.map_current_in_place(|message| {
+            if let Err(error) = (self.validate)(message) {
+                *message = (self.to_error)(error);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec::Vec};
+
+    #[derive(Debug, PartialEq)]
+    enum TestMessage {
+        Divide(f64),
+        Error(String),
+    }
+
+    fn validate(message: &TestMessage) -> Result<(), String> {
+        match message {
+            TestMessage::Divide(value) if 0.0 == *value => Err("Division by zero".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn test_invalid_message_becomes_error() {
+        let mut system = ValidationSystem::new(validate, TestMessage::Error);
+        let mut messages = MessageQueue::new();
+        messages.push(TestMessage::Divide(2.0));
+        messages.push(TestMessage::Divide(0.0));
+        messages.push(TestMessage::Divide(4.0));
+        messages.next_tick();
+
+        system.update(&mut (), &mut messages);
+
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            [
+                &TestMessage::Divide(2.0),
+                &TestMessage::Error("Division by zero".to_string()),
+                &TestMessage::Divide(4.0),
+            ]
+        );
+    }
+}
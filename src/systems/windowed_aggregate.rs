@@ -0,0 +1,102 @@
+// src/systems/windowed_aggregate.rs
+
+// `WindowedAggregateSystem` supports rolling computations such as smoothing a sensor signal.
+// It extracts a value from every matching current-tick message and keeps the last `window`
+// values, oldest first. Each tick, once at least one value has been seen, the aggregate
+// function reduces the window (to a mean, minimum, maximum or anything else) into a message
+// that is pushed for the next tick.
+
+use crate::{message_queue::MessageQueue, system::System};
+use alloc::collections::VecDeque;
+
+pub struct WindowedAggregateSystem<Message, Value> {
+    window: usize,
+    value_of: fn(&Message) -> Option<Value>,
+    aggregate: fn(&[Value]) -> Message,
+    values: VecDeque<Value>,
+}
+
+impl<Message, Value> WindowedAggregateSystem<Message, Value> {
+    pub fn new(
+        window: usize,
+        value_of: fn(&Message) -> Option<Value>,
+        aggregate: fn(&[Value]) -> Message,
+    ) -> Self {
+        assert!(0 < window, "Aggregate window must hold at least one value.");
+        Self {
+            window,
+            value_of,
+            aggregate,
+            values: VecDeque::with_capacity(window),
+        }
+    }
+}
+
+impl<ProgramState, Message, Value> System<ProgramState, Message>
+    for WindowedAggregateSystem<Message, Value>
+{
+    fn update(&mut self, _program_state: &mut ProgramState, messages: &mut MessageQueue<Message>) {
+        for value in messages.iter().filter_map(self.value_of) {
+            if self.values.len() == self.window {
+                self.values.pop_front();
+            }
+            self.values.push_back(value);
+        }
+        if !self.values.is_empty() {
+            messages.push((self.aggregate)(self.values.make_contiguous()));
+        }
+    }
+
+    fn push_hint(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestMessage {
+        Sample(f64),
+        Mean(f64),
+    }
+
+    fn sample(message: &TestMessage) -> Option<f64> {
+        match message {
+            TestMessage::Sample(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn mean(values: &[f64]) -> TestMessage {
+        TestMessage::Mean(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    #[test]
+    fn test_windowed_mean() {
+        let mut system = WindowedAggregateSystem::new(3, sample, mean);
+        let mut messages = MessageQueue::new();
+        let mut means = Vec::new();
+
+        for value in [3.0, 6.0, 9.0, 12.0, 30.0] {
+            messages.push(TestMessage::Sample(value));
+            messages.next_tick();
+            system.update(&mut (), &mut messages);
+            means.extend(messages.peek_next().cloned());
+            messages.next_tick_queue.clear();
+        }
+
+        assert_eq!(
+            means,
+            [
+                TestMessage::Mean(3.0),
+                TestMessage::Mean(4.5),
+                TestMessage::Mean(6.0),
+                TestMessage::Mean(9.0),
+                TestMessage::Mean(17.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// tests/abort_hook.rs
+
+// Runs the abort hook from a real panic. A test binary unwinds instead of aborting, so the
+// process-wide panic hook stands in for the `#[panic_handler]` of a `no_std` program. This file
+// is its own test binary, so replacing the panic hook does not affect other tests.
+
+#![cfg(feature = "testing")]
+
+extern crate flight_brain;
+
+use flight_brain::{
+    message_queue::MessageQueue,
+    run::{call_abort_hook, run_with_abort_hook, AbortHook},
+    system::System,
+};
+use std::{
+    panic,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static TRIPPED: AtomicBool = AtomicBool::new(false);
+static WATCHDOG: AbortHook = AbortHook::new(trip_watchdog);
+
+fn trip_watchdog() {
+    TRIPPED.store(true, Ordering::SeqCst);
+}
+
+struct PanickingSystem;
+
+impl System<(), ()> for PanickingSystem {
+    fn update(&mut self, _program_state: &mut (), _message_queue: &mut MessageQueue<()>) {
+        panic!("System failed.");
+    }
+}
+
+#[test]
+fn test_abort_hook_runs_on_panic() {
+    panic::set_hook(Box::new(|_| call_abort_hook()));
+    let update_func = |_program_state: &mut (),
+                       _message_queue: &mut MessageQueue<()>,
+                       systems: Vec<Box<dyn System<(), ()>>>| {
+        if systems.is_empty() {
+            vec![Box::new(PanickingSystem) as Box<dyn System<(), ()>>]
+        } else {
+            systems
+        }
+    };
+
+    let result = panic::catch_unwind(|| {
+        run_with_abort_hook((), MessageQueue::new(), update_func, &WATCHDOG);
+    });
+    let _ = panic::take_hook();
+
+    assert!(result.is_err());
+    assert!(TRIPPED.load(Ordering::SeqCst));
+    // Unwinding out of the loop unregistered the hook.
+    TRIPPED.store(false, Ordering::SeqCst);
+    call_abort_hook();
+    assert!(!TRIPPED.load(Ordering::SeqCst));
+}